@@ -1,9 +1,15 @@
-use darling::{ast::NestedMeta, FromMeta};
+use darling::{
+    FromDeriveInput, FromField, FromMeta, FromVariant,
+    ast::NestedMeta,
+    util::{Flag, Override},
+};
 use proc_macro::{Span, TokenStream};
+use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 use syn::{
+    Attribute, Ident, ItemImpl, Token,
     parse::{Error, ParseStream},
-    parse_macro_input, Attribute, Ident, ItemImpl, Token,
+    parse_macro_input,
 };
 
 /// All of the arguments that can be passed to the `api_endpoint` macro.
@@ -18,17 +24,56 @@ struct APIEndpointArgs {
     self_as_body: Option<String>,
     /// Serialize the struct as the body, assumes content-type header is `application/protobuf`.
     prost_self_as_body: Option<syn::Type>,
-    /// Deserialize the response as Protobuf.
+    /// The type of a successful response, which implements `TypedEndpoint`.
+    response: Option<TypeArg>,
+    /// Deserialize the response as Protobuf, requires `response`.
     prost_response: Option<bool>,
     /// Whether to ignore errors from the response.
     ignore_errors: Option<bool>,
+    /// How long to wait for the response, e.g. `"500ms"`, `"5s"`, `"2m"` or `"1h"`.
+    timeout: Option<syn::LitStr>,
 }
 
 /// All of the arguments that can be passed to the `api_rest_client` macro.
 #[derive(Debug, FromMeta)]
 struct APIRestClientArgs {
     error: Option<Ident>,
+    /// A constant base URL.
     base: Option<syn::Expr>,
+    /// A field holding the `BaseUrl`.
+    base_field: Option<Ident>,
+    /// An environment variable holding the base URL, which takes priority over `base` and `environments`.
+    base_env: Option<String>,
+    /// Named base URLs, e.g. `environments(production = "...", sandbox = "...")`.
+    environments: Option<Environments>,
+    /// The environment to use, the first by default.
+    environment: Option<String>,
+    /// An environment variable holding the name of the environment to use.
+    environment_env: Option<String>,
+}
+
+/// Named base URLs, in the order they were given.
+#[derive(Debug)]
+struct Environments(Vec<(String, String)>);
+impl FromMeta for Environments {
+    fn from_list(items: &[NestedMeta]) -> darling::Result<Self> {
+        let mut environments = Vec::new();
+        for item in items {
+            let NestedMeta::Meta(syn::Meta::NameValue(pair)) = item else {
+                return Err(darling::Error::custom("expected `name = \"url\"`").with_span(item));
+            };
+            let name = pair
+                .path
+                .get_ident()
+                .ok_or_else(|| darling::Error::custom("expected a name").with_span(&pair.path))?;
+            let url = String::from_expr(&pair.value)?;
+            environments.push((name.to_string(), url));
+        }
+        if environments.is_empty() {
+            return Err(darling::Error::too_few_items(1));
+        }
+        Ok(Self(environments))
+    }
 }
 
 /// Inspired from `async-trait`
@@ -81,7 +126,7 @@ pub fn api_endpoint(args: TokenStream, input: TokenStream) -> TokenStream {
     let mut impl_input = parse_macro_input!(input as ParseItemImpl);
 
     // The implementation for each
-    let method = _args.method.map(|m| {
+    let method = _args.method.as_ref().map(|m| {
         quote! {
             fn method(&self) -> ::api_builder::Method {
                 ::api_builder::Method::#m
@@ -99,16 +144,35 @@ pub fn api_endpoint(args: TokenStream, input: TokenStream) -> TokenStream {
     });
     add_impl_input!(impl_input, ignore_errors);
 
+    let timeout = match _args.timeout.map(|t| parse_duration(&t)).transpose() {
+        Ok(v) => v.map(|millis| {
+            quote! {
+                fn timeout(&self) -> Option<::std::time::Duration> {
+                    Some(::std::time::Duration::from_millis(#millis))
+                }
+            }
+        }),
+        Err(e) => return TokenStream::from(e.to_compile_error()),
+    };
+    add_impl_input!(impl_input, timeout);
+
+    let route = _args.path.as_ref().and_then(path_template).map(|path| {
+        let method = _args
+            .method
+            .clone()
+            .unwrap_or_else(|| Ident::new("GET", Span::call_site().into()));
+        (method, path)
+    });
     let path = _args.path.map(|p| {
         // Check if it's a string literal
-        if let syn::Expr::Lit(lit) = &p {
-            if let syn::Lit::Str(s) = &lit.lit {
-                return quote! {
-                    fn path(&self) -> ::std::borrow::Cow<'static, str> {
-                        ::std::borrow::Cow::Borrowed(#s)
-                    }
-                };
-            }
+        if let syn::Expr::Lit(lit) = &p
+            && let syn::Lit::Str(s) = &lit.lit
+        {
+            return quote! {
+                fn path(&self) -> ::std::borrow::Cow<'static, str> {
+                    ::std::borrow::Cow::Borrowed(#s)
+                }
+            };
         }
 
         // Additonal checks could be added like checking for constants or something...
@@ -135,21 +199,197 @@ pub fn api_endpoint(args: TokenStream, input: TokenStream) -> TokenStream {
         }));
     add_impl_input!(impl_input, body);
 
-    if _args.prost_response.unwrap_or_default() {
-        impl_input.0.items.push(syn::ImplItem::Verbatim(quote! {
-            fn deserialize(&self, response: ::api_builder::Response<::api_builder::Bytes>) -> Result<Self::Response, ::api_builder::error::BodyError> {
-                use prost::Message;
-                Self::Response::decode(response.into_body()).map_err(|_| ::api_builder::error::BodyError::Deserialize)
+    // The response type and route live in their own traits, since associated types can't have defaults
+    let (impl_generics, _, where_clause) = impl_input.0.generics.split_for_impl();
+    let self_ty = &impl_input.0.self_ty;
+    // Keep the impl's `cfg` and lint attributes, e.g. `#[allow(deprecated)]`
+    let attrs = impl_input
+        .0
+        .attrs
+        .iter()
+        .filter(|x| {
+            ["cfg", "allow", "expect"]
+                .iter()
+                .any(|name| x.path().is_ident(name))
+        })
+        .collect::<Vec<_>>();
+    let prost_response = _args.prost_response.unwrap_or_default();
+    let typed = match _args.response {
+        Some(TypeArg(response)) => {
+            let decode = if prost_response {
+                quote! {
+                    <#response as ::prost::Message>::decode(response.into_body()).map_err(|_| ::api_builder::error::BodyError::Deserialize)
+                }
+            } else {
+                quote! {
+                    ::api_builder::Endpoint::deserialize(self, response)
+                }
+            };
+            Some(quote! {
+                #(#attrs)*
+                impl #impl_generics ::api_builder::TypedEndpoint for #self_ty #where_clause {
+                    type Response = #response;
+
+                    fn decode(&self, response: ::api_builder::Response<::api_builder::Bytes>) -> Result<Self::Response, ::api_builder::error::BodyError> {
+                        #decode
+                    }
+                }
+            })
+        }
+        None if prost_response => {
+            return TokenStream::from(
+                Error::new(
+                    Span::call_site().into(),
+                    "`prost_response` requires the `response` type",
+                )
+                .to_compile_error(),
+            );
+        }
+        None => None,
+    };
+
+    let route = route.map(|(method, path)| {
+        quote! {
+            #(#attrs)*
+            impl #impl_generics ::api_builder::EndpointRoute for #self_ty #where_clause {
+                const METHOD: ::api_builder::Method = ::api_builder::Method::#method;
+                const PATH: &'static str = #path;
             }
-        }));
-    }
+        }
+    });
 
     // Return the input
-    let inner_impl = impl_input.0;
-    TokenStream::from(quote!(#inner_impl))
+    let inner_impl = &impl_input.0;
+    TokenStream::from(quote! {
+        #inner_impl
+        #typed
+        #route
+    })
+}
+
+/// The path template of a path expression, e.g. `/pets/{id}` for `format!("pets/{}", self.id)`.
+///
+/// Only string literals and `format!` of fields or variables are understood.
+fn path_template(path: &syn::Expr) -> Option<String> {
+    let template = match path {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(s),
+            ..
+        }) => s.value(),
+        syn::Expr::Macro(m) if m.mac.path.is_ident("format") => {
+            let args = m
+                .mac
+                .parse_body_with(
+                    syn::punctuated::Punctuated::<syn::Expr, Token![,]>::parse_terminated,
+                )
+                .ok()?;
+            let mut args = args.into_iter();
+            let syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(format),
+                ..
+            }) = args.next()?
+            else {
+                return None;
+            };
+            let names = args
+                .map(|arg| match arg {
+                    syn::Expr::Field(syn::ExprField {
+                        member: syn::Member::Named(name),
+                        ..
+                    }) => Some(name.to_string()),
+                    syn::Expr::Path(path) => path.path.get_ident().map(|x| x.to_string()),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()?;
+            format_template(&format.value(), &names)?
+        }
+        _ => return None,
+    };
+    Some(match template.starts_with('/') {
+        true => template,
+        false => format!("/{template}"),
+    })
+}
+
+/// Replaces the arguments of a format string with `{name}`.
+fn format_template(format: &str, names: &[String]) -> Option<String> {
+    let mut template = String::with_capacity(format.len());
+    let mut next = 0;
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                let argument = chars.by_ref().take_while(|&c| c != '}').collect::<String>();
+                if argument.starts_with('{') {
+                    return None;
+                }
+                let argument = argument.split(':').next().unwrap_or_default().trim();
+                let name = if argument.is_empty() {
+                    next += 1;
+                    names.get(next - 1)?.as_str()
+                } else if let Ok(index) = argument.parse::<usize>() {
+                    names.get(index)?.as_str()
+                } else {
+                    argument
+                };
+                template.push('{');
+                template.push_str(name.trim_start_matches("r#"));
+                template.push('}');
+            }
+            '}' => return None,
+            c => template.push(c),
+        }
+    }
+    Some(template)
+}
+
+/// Parses a duration such as `"5s"` into milliseconds.
+fn parse_duration(lit: &syn::LitStr) -> syn::Result<u64> {
+    let value = lit.value();
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+
+    let multiplier = match unit.trim() {
+        "ms" => 1,
+        "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        _ => {
+            return Err(Error::new(
+                lit.span(),
+                "expected a duration such as \"500ms\", \"5s\", \"2m\" or \"1h\"",
+            ));
+        }
+    };
+    amount
+        .parse::<u64>()
+        .ok()
+        .and_then(|x| x.checked_mul(multiplier))
+        .ok_or_else(|| Error::new(lit.span(), "invalid duration"))
+}
+
+/// Checks a constant base URL in the same way as `BaseUrl::parse`.
+fn check_base_url(input: &str) -> Result<(), String> {
+    let url = url::Url::parse(input).map_err(|e| format!("failed to parse the base URL: {e}"))?;
+    if url.cannot_be_a_base() {
+        return Err(format!("the base URL cannot have a path appended: {url}"));
+    }
+    Ok(())
 }
 
 /// Implements `RestClient`.
+///
+/// The base URL is given by one of:
+/// - `base = "\"https://example.com/v1\""`, a constant.
+/// - `base_field = base`, a `BaseUrl` field which is validated when the client is created.
+/// - `environments(production = "...", sandbox = "...")`, choosing `environment = "..."` or the first, optionally overridden by the environment variable `environment_env = "..."`.
+///
+/// `base_env = "..."` names an environment variable which overrides `base` or `environments`.
+/// Constant URLs are checked at compile time.
+/// Except for `base_field`, the base URL is resolved once, and `base_url()` and `checked()` are added to the client to validate it when the client is created.
 #[proc_macro_attribute]
 pub fn api_rest_client(args: TokenStream, input: TokenStream) -> TokenStream {
     // Parse the input
@@ -168,25 +408,143 @@ pub fn api_rest_client(args: TokenStream, input: TokenStream) -> TokenStream {
     let mut impl_input = parse_macro_input!(input as ParseItemImpl);
 
     // The implementation for each
-    let error = _args.error.map(|e| {
+    let error = _args.error.as_ref().map(|e| {
         quote! {
             type Error = #e;
         }
     });
     add_impl_input!(impl_input, error);
 
-    let base = _args.base
-        .map(|b| quote! {
-            fn rest_endpoint(&self, path: &str) -> Result<api_builder::Url, ::api_builder::error::APIError<Self::Error>> {
-                let url = ::api_builder::Url::parse(#b)?;
-                Ok(url.join(path)?)
-            }
-        });
-    add_impl_input!(impl_input, base);
+    let base = match base_tokens(&_args, &impl_input.0) {
+        Ok(v) => v,
+        Err(e) => return TokenStream::from(e.to_compile_error()),
+    };
+    let (rest_endpoint, base_url) = base.unzip();
+    add_impl_input!(impl_input, rest_endpoint);
 
     // Return the input
     let inner_impl = impl_input.0;
-    TokenStream::from(quote!(#inner_impl))
+    TokenStream::from(quote! {
+        #inner_impl
+        #base_url
+    })
+}
+
+/// The `rest_endpoint` method for the base URL, and the `base_url` function if it is resolved once.
+fn base_tokens(
+    args: &APIRestClientArgs,
+    item: &ItemImpl,
+) -> syn::Result<Option<(TokenStream2, Option<TokenStream2>)>> {
+    let error = |message: &str| Error::new(Span::call_site().into(), message);
+
+    // A field, which was validated when it was created
+    if let Some(field) = &args.base_field {
+        if args.base.is_some() || args.base_env.is_some() || args.environments.is_some() {
+            return Err(error(
+                "`base_field` can't be used with `base`, `base_env` or `environments`",
+            ));
+        }
+        return Ok(Some((
+            quote! {
+                fn rest_endpoint(&self, target: &::api_builder::RequestTarget<'_>) -> Result<::api_builder::Uri, ::api_builder::error::APIError<Self::Error>> {
+                    Ok(self.#field.join(target)?)
+                }
+            },
+            None,
+        )));
+    }
+
+    // Otherwise the base is resolved once, after checking any constant URLs
+    if let Some(syn::Expr::Lit(syn::ExprLit {
+        lit: syn::Lit::Str(base),
+        ..
+    })) = &args.base
+    {
+        check_base_url(&base.value()).map_err(|e| Error::new(base.span(), e))?;
+    }
+    let environments = match &args.environments {
+        Some(Environments(environments)) => {
+            if args.base.is_some() {
+                return Err(error("`base` can't be used with `environments`"));
+            }
+            for (name, url) in environments {
+                check_base_url(url).map_err(|e| error(&format!("environment `{name}`: {e}")))?;
+            }
+            let default = match &args.environment {
+                Some(name) => {
+                    if !environments
+                        .iter()
+                        .any(|(x, _)| x.eq_ignore_ascii_case(name))
+                    {
+                        return Err(error(&format!("unknown environment `{name}`")));
+                    }
+                    name.clone()
+                }
+                None => environments[0].0.clone(),
+            };
+            let name = match &args.environment_env {
+                Some(variable) => quote! {
+                    &::std::env::var(#variable).unwrap_or_else(|_| #default.to_owned())
+                },
+                None => quote!(#default),
+            };
+            let (names, urls): (Vec<_>, Vec<_>) = environments.iter().cloned().unzip();
+            Some(quote! {
+                ::api_builder::BaseUrl::environment(#name, &[#((#names, #urls)),*])
+            })
+        }
+        None if args.environment.is_some() || args.environment_env.is_some() => {
+            return Err(error(
+                "`environment` and `environment_env` require `environments`",
+            ));
+        }
+        None => None,
+    };
+    let fallback = environments.or_else(|| {
+        args.base
+            .as_ref()
+            .map(|base| quote!(::api_builder::BaseUrl::parse(#base)))
+    });
+    let resolve = match (&args.base_env, fallback) {
+        (Some(variable), Some(fallback)) => quote! {
+            match ::std::env::var(#variable) {
+                Ok(url) => ::api_builder::BaseUrl::parse(&url),
+                Err(_) => #fallback,
+            }
+        },
+        (Some(variable), None) => quote!(::api_builder::BaseUrl::from_env(#variable)),
+        (None, Some(fallback)) => fallback,
+        (None, None) => return Ok(None),
+    };
+
+    let (impl_generics, _, where_clause) = item.generics.split_for_impl();
+    let self_ty = &item.self_ty;
+    Ok(Some((
+        quote! {
+            fn rest_endpoint(&self, target: &::api_builder::RequestTarget<'_>) -> Result<::api_builder::Uri, ::api_builder::error::APIError<Self::Error>> {
+                Ok(Self::base_url()?.join(target)?)
+            }
+        },
+        Some(quote! {
+            impl #impl_generics #self_ty #where_clause {
+                /// The base URL of the client, which is resolved and validated once.
+                ///
+                /// Call this when creating the client to find an invalid base URL early.
+                pub fn base_url() -> Result<&'static ::api_builder::BaseUrl, ::api_builder::error::BaseUrlError> {
+                    static BASE: ::std::sync::OnceLock<Result<::api_builder::BaseUrl, ::api_builder::error::BaseUrlError>> = ::std::sync::OnceLock::new();
+                    BASE.get_or_init(|| #resolve).as_ref().map_err(Clone::clone)
+                }
+
+                /// Returns the client once its base URL has been resolved and validated.
+                ///
+                /// For example, `Client { .. }.checked()?` fails when an environment variable holds an invalid URL.
+                pub fn checked(self) -> Result<Self, ::api_builder::error::BaseUrlError> {
+                    Self::base_url()?;
+                    Ok(self)
+                }
+            }
+        }),
+    )))
 }
 
 /// Implements `ReqwestClient`, assumes that the struct has a `client` field.
@@ -222,3 +580,571 @@ pub fn derive_reqwest_async_client(input: TokenStream) -> TokenStream {
         }
     })
 }
+
+/// Implements `HyperAsyncClient` and `AsyncClient`, assumes that the struct has a `hyper_client` field.
+///
+/// The field must be a `hyper_util::client::legacy::Client<Connector, Full<Bytes>>` (or `HyperClient<Connector>`).
+#[proc_macro_derive(HyperAsyncClient)]
+pub fn derive_hyper_async_client(input: TokenStream) -> TokenStream {
+    // Parse the input
+    let input = parse_macro_input!(input as syn::ItemStruct);
+    let name = input.ident;
+
+    // Find the connector from the field's type
+    let field = input
+        .fields
+        .iter()
+        .find(|f| f.ident.as_ref().is_some_and(|i| i == "hyper_client"));
+    let Some(field) = field else {
+        return TokenStream::from(
+            Error::new(Span::call_site().into(), "expected a `hyper_client` field")
+                .to_compile_error(),
+        );
+    };
+    let connector = match &field.ty {
+        syn::Type::Path(path) => path.path.segments.last().and_then(|s| match &s.arguments {
+            syn::PathArguments::AngleBracketed(args) => args.args.iter().find_map(|a| match a {
+                syn::GenericArgument::Type(ty) => Some(ty.clone()),
+                _ => None,
+            }),
+            _ => None,
+        }),
+        _ => None,
+    };
+    let Some(connector) = connector else {
+        return TokenStream::from(
+            Error::new_spanned(&field.ty, "expected a hyper client with a connector type")
+                .to_compile_error(),
+        );
+    };
+
+    // Return the input
+    TokenStream::from(quote! {
+        impl ::api_builder::client::HyperAsyncClient for #name {
+            type Connector = #connector;
+
+            fn client(&self) -> &::api_builder::client::HyperClient<Self::Connector> {
+                &self.hyper_client
+            }
+        }
+
+        impl ::api_builder::client::AsyncClient for #name {
+            async fn rest_async(
+                &self,
+                request: ::api_builder::Request<Vec<u8>>,
+            ) -> Result<::api_builder::Response<::api_builder::Bytes>, ::api_builder::error::APIError<Self::Error>> {
+                ::api_builder::client::HyperAsyncClient::rest_hyper(self, request).await
+            }
+        }
+    })
+}
+
+/// Implements `UreqClient` and `Client`, assumes that the struct has a `ureq_agent` field.
+#[proc_macro_derive(UreqClient)]
+pub fn derive_ureq_client(input: TokenStream) -> TokenStream {
+    // Parse the input
+    let input = parse_macro_input!(input as syn::ItemStruct);
+    let name = input.ident;
+
+    // Return the input
+    TokenStream::from(quote! {
+        impl ::api_builder::client::UreqClient for #name {
+            fn client(&self) -> &::ureq::Agent {
+                &self.ureq_agent
+            }
+        }
+
+        impl ::api_builder::client::Client for #name {
+            fn rest(
+                &self,
+                request: ::api_builder::Request<Vec<u8>>,
+            ) -> Result<::api_builder::Response<::api_builder::Bytes>, ::api_builder::error::APIError<Self::Error>> {
+                ::api_builder::client::UreqClient::rest_ureq(self, request)
+            }
+
+            fn rest_body(
+                &self,
+                request: ::api_builder::Request<::api_builder::RequestBody>,
+            ) -> Result<::api_builder::Response<::api_builder::Bytes>, ::api_builder::error::APIError<Self::Error>> {
+                ::api_builder::client::UreqClient::rest_body_ureq(self, request)
+            }
+        }
+    })
+}
+
+/// All of the arguments that can be passed to `#[describe(...)]` on an endpoint.
+#[derive(Debug, FromDeriveInput)]
+#[darling(
+    attributes(describe),
+    forward_attrs(doc, deprecated),
+    supports(struct_named, struct_unit)
+)]
+struct DescribeEndpointArgs {
+    ident: Ident,
+    generics: syn::Generics,
+    attrs: Vec<Attribute>,
+    data: darling::ast::Data<(), DescribeFieldArgs>,
+    /// The HTTP method, from `#[api_endpoint(...)]` by default.
+    method: Option<Ident>,
+    /// The path template, e.g. `"/pets/{id}"`, from `#[api_endpoint(...)]` by default.
+    path: Option<String>,
+    /// The operation ID, the struct name by default.
+    operation_id: Option<String>,
+    /// The whole struct is the body, with this content type.
+    self_as_body: Option<String>,
+    /// The type of the successful response.
+    response: Option<TypeArg>,
+    /// The content type of the response, `application/json` by default.
+    response_content_type: Option<String>,
+    /// The status of the successful response, `200` by default.
+    status: Option<syn::LitInt>,
+}
+
+/// A type, given as a path such as `Pet` or a string such as `"Vec<Pet>"`.
+#[derive(Debug)]
+struct TypeArg(syn::Type);
+impl FromMeta for TypeArg {
+    fn from_expr(expr: &syn::Expr) -> darling::Result<Self> {
+        match expr {
+            syn::Expr::Path(path) => Ok(Self(syn::Type::Path(syn::TypePath {
+                qself: path.qself.clone(),
+                path: path.path.clone(),
+            }))),
+            _ => syn::Type::from_expr(expr).map(Self),
+        }
+    }
+}
+
+/// All of the arguments that can be passed to `#[describe(...)]` on a field.
+#[derive(Debug, FromField)]
+#[darling(attributes(describe), forward_attrs(doc))]
+struct DescribeFieldArgs {
+    ident: Option<Ident>,
+    ty: syn::Type,
+    attrs: Vec<Attribute>,
+    /// The field is a path parameter.
+    path: Flag,
+    /// The field is a query parameter.
+    query: Flag,
+    /// The field is a header.
+    header: Flag,
+    /// The field is a cookie.
+    cookie: Flag,
+    /// The field is the body, optionally with a content type other than `application/json`.
+    body: Option<Override<String>>,
+    /// The name of the parameter, the field name by default.
+    rename: Option<String>,
+}
+
+/// Collects the doc comments into a summary and description.
+fn doc_comments(attrs: &[Attribute]) -> (Option<String>, Option<String>) {
+    let lines = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(s),
+                        ..
+                    }),
+                ..
+            }) => Some(s.value()),
+            _ => None,
+        })
+        .map(|line| {
+            line.strip_prefix(' ')
+                .unwrap_or(&line)
+                .trim_end()
+                .to_owned()
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut parts = lines.trim().splitn(2, "\n\n");
+    let summary = parts
+        .next()
+        .filter(|x| !x.is_empty())
+        .map(|x| x.replace('\n', " "));
+    let description = parts
+        .next()
+        .map(|x| x.trim().to_owned())
+        .filter(|x| !x.is_empty());
+    (summary, description)
+}
+
+/// Get `T` from `Option<T>`.
+fn option_inner(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        syn::GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    }
+}
+
+/// Quotes an optional string as an `Option<Cow<'static, str>>`.
+fn quote_option_str(value: Option<String>) -> impl quote::ToTokens {
+    match value {
+        Some(value) => quote!(Some(::std::borrow::Cow::Borrowed(#value))),
+        None => quote!(None),
+    }
+}
+
+/// Implements `DescribeEndpoint`, describing the endpoint in OpenAPI.
+///
+/// The method and path template are taken from `#[api_endpoint(...)]` through `EndpointRoute`, or can be given with `#[describe(method = POST, path = "/pets/{id}")]`.
+/// The struct may also set `operation_id`, `self_as_body`, `response`, `response_content_type` and `status`.
+/// Fields are parameters when marked with `#[describe(path)]`, `#[describe(query)]`, `#[describe(header)]` or `#[describe(cookie)]`, optionally with `rename = "..."`.
+/// A field marked with `#[describe(body)]` or `#[describe(body = "content/type")]` is the body.
+/// Doc comments become the summary and descriptions.
+#[proc_macro_derive(DescribeEndpoint, attributes(describe))]
+pub fn derive_describe_endpoint(input: TokenStream) -> TokenStream {
+    // Parse the input
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    let args = match DescribeEndpointArgs::from_derive_input(&input) {
+        Ok(v) => v,
+        Err(e) => return TokenStream::from(e.write_errors()),
+    };
+    let name = &args.ident;
+    let (impl_generics, ty_generics, where_clause) = args.generics.split_for_impl();
+
+    // The parameters and body from the fields
+    let mut parameters = Vec::new();
+    let mut body = args.self_as_body.map(|content_type| {
+        quote! {
+            Some(::api_builder::openapi::MediaDescription {
+                content_type: ::std::borrow::Cow::Borrowed(#content_type),
+                required: true,
+                schema: generator.subschema_for::<Self>(),
+            })
+        }
+    });
+    let fields = match args.data {
+        darling::ast::Data::Struct(fields) => fields.fields,
+        darling::ast::Data::Enum(_) => Vec::new(),
+    };
+    for field in fields {
+        let (ty, required) = match option_inner(&field.ty) {
+            Some(ty) => (ty, false),
+            None => (&field.ty, true),
+        };
+
+        if let Some(content_type) = field.body {
+            if body.is_some() {
+                return TokenStream::from(
+                    Error::new_spanned(&field.ty, "only one body is allowed").to_compile_error(),
+                );
+            }
+            let content_type = content_type.unwrap_or_else(|| "application/json".to_owned());
+            body = Some(quote! {
+                Some(::api_builder::openapi::MediaDescription {
+                    content_type: ::std::borrow::Cow::Borrowed(#content_type),
+                    required: #required,
+                    schema: generator.subschema_for::<#ty>(),
+                })
+            });
+            continue;
+        }
+
+        let location = match (
+            field.path.is_present(),
+            field.query.is_present(),
+            field.header.is_present(),
+            field.cookie.is_present(),
+        ) {
+            (true, false, false, false) => quote!(Path),
+            (false, true, false, false) => quote!(Query),
+            (false, false, true, false) => quote!(Header),
+            (false, false, false, true) => quote!(Cookie),
+            (false, false, false, false) => continue,
+            _ => {
+                return TokenStream::from(
+                    Error::new_spanned(&field.ty, "a parameter can only have one location")
+                        .to_compile_error(),
+                );
+            }
+        };
+        let parameter = field.rename.unwrap_or_else(|| {
+            field
+                .ident
+                .as_ref()
+                .map(|x| x.to_string().trim_start_matches("r#").to_owned())
+                .unwrap_or_default()
+        });
+        let required = required || field.path.is_present();
+        let (summary, description) = doc_comments(&field.attrs);
+        let description = quote_option_str(match (summary, description) {
+            (Some(summary), Some(description)) => Some(format!("{summary}\n\n{description}")),
+            (summary, description) => summary.or(description),
+        });
+        parameters.push(quote! {
+            ::api_builder::openapi::ParameterDescription {
+                name: ::std::borrow::Cow::Borrowed(#parameter),
+                location: ::api_builder::openapi::ParameterLocation::#location,
+                required: #required,
+                description: #description,
+                schema: generator.subschema_for::<#ty>(),
+            }
+        });
+    }
+    let body = body.unwrap_or_else(|| quote!(None));
+
+    // The rest of the description
+    let method = match args.method {
+        Some(method) => quote!(::api_builder::Method::#method),
+        None => quote!(<Self as ::api_builder::EndpointRoute>::METHOD),
+    };
+    let path = match args.path {
+        Some(path) => quote!(#path),
+        None => quote!(<Self as ::api_builder::EndpointRoute>::PATH),
+    };
+    let status = match args.status {
+        Some(status) => match status.base10_parse::<u16>() {
+            Ok(value @ 100..=999) => value,
+            _ => {
+                return TokenStream::from(
+                    Error::new_spanned(status, "expected a status between 100 and 999")
+                        .to_compile_error(),
+                );
+            }
+        },
+        None => 200,
+    };
+    let operation_id = args.operation_id.unwrap_or_else(|| name.to_string());
+    let (summary, description) = doc_comments(&args.attrs);
+    let summary = quote_option_str(summary);
+    let description = quote_option_str(description);
+    let deprecated = args
+        .attrs
+        .iter()
+        .any(|attr| attr.path().is_ident("deprecated"));
+    let response = args.response.map(|TypeArg(ty)| {
+        let content_type = args
+            .response_content_type
+            .unwrap_or_else(|| "application/json".to_owned());
+        quote! {
+            Some(::api_builder::openapi::MediaDescription {
+                content_type: ::std::borrow::Cow::Borrowed(#content_type),
+                required: true,
+                schema: generator.subschema_for::<#ty>(),
+            })
+        }
+    });
+    let response = response.unwrap_or_else(|| quote!(None));
+
+    // Return the input
+    TokenStream::from(quote! {
+        impl #impl_generics ::api_builder::openapi::DescribeEndpoint for #name #ty_generics #where_clause {
+            fn describe(
+                generator: &mut ::api_builder::schemars::SchemaGenerator,
+            ) -> ::api_builder::openapi::EndpointDescription {
+                ::api_builder::openapi::EndpointDescription {
+                    method: #method,
+                    path: ::std::borrow::Cow::Borrowed(#path),
+                    operation_id: Some(::std::borrow::Cow::Borrowed(#operation_id)),
+                    summary: #summary,
+                    description: #description,
+                    deprecated: #deprecated,
+                    parameters: vec![#(#parameters),*],
+                    body: #body,
+                    response: #response,
+                    status: ::api_builder::StatusCode::from_u16(#status).unwrap_or_default(),
+                }
+            }
+        }
+    })
+}
+
+/// All of the arguments that can be passed to `#[query_value(...)]` on an enum.
+#[derive(Debug, FromDeriveInput)]
+#[darling(attributes(query_value), supports(enum_unit))]
+struct ToQueryValueArgs {
+    ident: Ident,
+    generics: syn::Generics,
+    data: darling::ast::Data<ToQueryValueVariant, ()>,
+    /// How to rename every variant, e.g. `"snake_case"`.
+    rename_all: Option<syn::LitStr>,
+}
+
+/// All of the arguments that can be passed to `#[query_value(...)]` on a variant.
+#[derive(Debug, FromVariant)]
+#[darling(attributes(query_value))]
+struct ToQueryValueVariant {
+    ident: Ident,
+    /// The value of the variant, the renamed variant name by default.
+    rename: Option<String>,
+}
+
+/// Renames a variant according to a `serde` style `rename_all` rule.
+///
+/// This follows `serde`, so every uppercase letter starts a new word, e.g. `HTTPServer` becomes `h_t_t_p_server`.
+fn rename_variant(rule: &syn::LitStr, variant: &str) -> syn::Result<String> {
+    let snake_case = || {
+        let mut snake = String::with_capacity(variant.len() * 2);
+        for (i, c) in variant.char_indices() {
+            if i > 0 && c.is_uppercase() {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        }
+        snake
+    };
+
+    Ok(match rule.value().as_str() {
+        "lowercase" => variant.to_ascii_lowercase(),
+        "UPPERCASE" => variant.to_ascii_uppercase(),
+        "PascalCase" => variant.to_owned(),
+        "camelCase" => {
+            // The first character may take more than one byte
+            let first = variant.chars().next().map_or(0, char::len_utf8);
+            variant[..first].to_ascii_lowercase() + &variant[first..]
+        }
+        "snake_case" => snake_case(),
+        "SCREAMING_SNAKE_CASE" => snake_case().to_ascii_uppercase(),
+        "kebab-case" => snake_case().replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => snake_case().to_ascii_uppercase().replace('_', "-"),
+        _ => {
+            return Err(Error::new(
+                rule.span(),
+                "expected one of \"lowercase\", \"UPPERCASE\", \"PascalCase\", \"camelCase\", \"snake_case\", \"SCREAMING_SNAKE_CASE\", \"kebab-case\" or \"SCREAMING-KEBAB-CASE\"",
+            ));
+        }
+    })
+}
+
+/// Implements `ToQueryValue` and `IntoQueryValue` for an enum of unit variants.
+///
+/// Each variant is sent as its name, which can be changed with `#[query_value(rename_all = "snake_case")]` on the enum or `#[query_value(rename = "...")]` on the variant.
+#[proc_macro_derive(ToQueryValue, attributes(query_value))]
+pub fn derive_to_query_value(input: TokenStream) -> TokenStream {
+    // Parse the input
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    let args = match ToQueryValueArgs::from_derive_input(&input) {
+        Ok(v) => v,
+        Err(e) => return TokenStream::from(e.write_errors()),
+    };
+    let name = &args.ident;
+    let (impl_generics, ty_generics, where_clause) = args.generics.split_for_impl();
+
+    // The value of each variant
+    let variants = match args.data {
+        darling::ast::Data::Enum(variants) => variants,
+        darling::ast::Data::Struct(_) => Vec::new(),
+    };
+    let mut into_generics = args.generics.clone();
+    into_generics.params.insert(0, syn::parse_quote!('__a));
+    let (into_impl_generics, _, _) = into_generics.split_for_impl();
+
+    let mut arms = Vec::new();
+    for variant in variants {
+        let ident = variant.ident;
+        let value = match (variant.rename, &args.rename_all) {
+            (Some(rename), _) => rename,
+            (None, Some(rule)) => match rename_variant(rule, &ident.to_string()) {
+                Ok(v) => v,
+                Err(e) => return TokenStream::from(e.to_compile_error()),
+            },
+            (None, None) => ident.to_string(),
+        };
+        arms.push(quote!(Self::#ident => #value));
+    }
+
+    // Return the input
+    TokenStream::from(quote! {
+        impl #impl_generics ::api_builder::ToQueryValue for #name #ty_generics #where_clause {
+            fn to_query_value(&self) -> Option<::std::borrow::Cow<'_, str>> {
+                Some(::std::borrow::Cow::Borrowed(match self {
+                    #(#arms,)*
+                }))
+            }
+        }
+
+        impl #into_impl_generics ::api_builder::IntoQueryValue<'__a> for #name #ty_generics #where_clause {
+            fn into_query_value(self) -> Option<::std::borrow::Cow<'__a, str>> {
+                Some(::std::borrow::Cow::Borrowed(match self {
+                    #(#arms,)*
+                }))
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(path: &str) -> Option<String> {
+        path_template(&syn::parse_str(path).unwrap())
+    }
+
+    #[test]
+    fn path_templates() {
+        assert_eq!(template(r#""pets""#).as_deref(), Some("/pets"));
+        assert_eq!(
+            template(r#"format!("pets/{}/toys/{}", self.id, self.toy)"#).as_deref(),
+            Some("/pets/{id}/toys/{toy}")
+        );
+        assert_eq!(
+            template(r#"format!("/pets/{1}/{0:?}", self.id, kind)"#).as_deref(),
+            Some("/pets/{kind}/{id}")
+        );
+        assert_eq!(
+            template(r#"format!("pets/{id}")"#).as_deref(),
+            Some("/pets/{id}")
+        );
+        assert_eq!(template(r#"format!("pets/{}", self.id + 1)"#), None);
+        assert_eq!(template(r#"format!("pets/{{id}}")"#), None);
+        assert_eq!(template("self.path.clone()"), None);
+    }
+
+    #[test]
+    fn checks_base_urls() {
+        assert!(check_base_url("https://example.com/v1").is_ok());
+        assert!(check_base_url("http://localhost:8080").is_ok());
+        assert!(check_base_url("example.com/v1").is_err());
+        assert!(check_base_url("mailto:pets@example.com").is_err());
+    }
+
+    #[test]
+    fn renames_variants_like_serde() {
+        let rename = |rule: &str, variant: &str| {
+            rename_variant(
+                &syn::LitStr::new(rule, proc_macro2::Span::call_site()),
+                variant,
+            )
+            .unwrap()
+        };
+        for (rule, expected) in [
+            ("lowercase", "httpserver"),
+            ("UPPERCASE", "HTTPSERVER"),
+            ("PascalCase", "HTTPServer"),
+            ("camelCase", "hTTPServer"),
+            ("snake_case", "h_t_t_p_server"),
+            ("SCREAMING_SNAKE_CASE", "H_T_T_P_SERVER"),
+            ("kebab-case", "h-t-t-p-server"),
+            ("SCREAMING-KEBAB-CASE", "H-T-T-P-SERVER"),
+        ] {
+            assert_eq!(rename(rule, "HTTPServer"), expected, "{rule}");
+        }
+        assert_eq!(rename("snake_case", "NewestFirst"), "newest_first");
+        assert_eq!(rename("camelCase", "NewestFirst"), "newestFirst");
+        // Like serde, only ASCII is lowercased
+        assert_eq!(rename("camelCase", "ÉtéFirst"), "ÉtéFirst");
+        assert!(
+            rename_variant(
+                &syn::LitStr::new("Title Case", proc_macro2::Span::call_site()),
+                "A"
+            )
+            .is_err()
+        );
+    }
+}
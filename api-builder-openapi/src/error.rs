@@ -0,0 +1,19 @@
+/// Errors that can occur when generating code.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The document is not valid YAML or JSON.
+    #[error(transparent)]
+    Parse(#[from] serde_norway::Error),
+    /// Reading the document or writing the output failed.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// The document is not OpenAPI 3.0 or 3.1.
+    #[error("unsupported OpenAPI version: {0}")]
+    UnsupportedVersion(String),
+    /// A `$ref` could not be resolved.
+    #[error("unresolved reference: {0}")]
+    UnresolvedReference(String),
+    /// The document is missing something, or has an unexpected shape.
+    #[error("invalid document: {0}")]
+    Invalid(String),
+}
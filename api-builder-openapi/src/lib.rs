@@ -0,0 +1,271 @@
+//! Generates [`api_builder`](https://docs.rs/api_builder) endpoints from OpenAPI 3.0 and 3.1 documents.
+//!
+//! Each operation becomes a struct with an `Endpoint` impl, using `api_endpoint` for the method and path.
+//! Path, query and header parameters become fields, as does the request body.
+//! Schemas become types, and the first successful JSON response of each operation becomes `<Operation>Response`, which is also its `TypedEndpoint::Response`.
+//! Finally, a `RestClient` skeleton forwards requests to any inner client.
+//!
+//! The generated code uses `serde` and `api_builder`, so both must be dependencies of the crate including it.
+use std::path::Path;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use serde_norway::Value;
+
+use crate::{
+    naming::{ident, type_name},
+    operation::{METHODS, Operation},
+    schema::{Document, Models, docs, string},
+};
+
+macro_rules! import {
+    ($($module:ident),*) => {
+        $(
+            mod $module;
+            pub use $module::*;
+        )*
+    };
+}
+import!(error);
+mod naming;
+mod operation;
+mod schema;
+
+/// Generates code from an OpenAPI document.
+#[derive(Clone, Debug)]
+pub struct Generator {
+    client: Option<String>,
+    base_url: Option<String>,
+}
+impl Default for Generator {
+    fn default() -> Self {
+        Self {
+            client: Some("Client".to_owned()),
+            base_url: None,
+        }
+    }
+}
+impl Generator {
+    /// Create a generator with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The name of the `RestClient` skeleton, `Client` by default.
+    pub fn client(mut self, name: impl Into<String>) -> Self {
+        self.client = Some(name.into());
+        self
+    }
+
+    /// Don't generate the `RestClient` skeleton.
+    pub fn without_client(mut self) -> Self {
+        self.client = None;
+        self
+    }
+
+    /// The base URL of the skeleton.
+    ///
+    /// Defaults to the first server in the document.
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = Some(url.into());
+        self
+    }
+
+    /// Generate code from a YAML or JSON document.
+    pub fn generate(&self, document: &str) -> Result<String, Error> {
+        let root: Value = serde_norway::from_str(document)?;
+        let tokens = self.generate_tokens(&root)?;
+        let file: syn::File = syn::parse2(tokens)
+            .map_err(|e| Error::Invalid(format!("generated invalid code: {e}")))?;
+        Ok(prettyplease::unparse(&file))
+    }
+
+    /// Generate code from the document at `input`, and write it to `output`.
+    ///
+    /// This is intended for `build.rs`, where `output` is usually within `OUT_DIR`.
+    pub fn generate_file(
+        &self,
+        input: impl AsRef<Path>,
+        output: impl AsRef<Path>,
+    ) -> Result<(), Error> {
+        let document = std::fs::read_to_string(input)?;
+        std::fs::write(output, self.generate(&document)?)?;
+        Ok(())
+    }
+
+    /// Generate the tokens for every item.
+    fn generate_tokens(&self, root: &Value) -> Result<TokenStream, Error> {
+        let version = match root.get("openapi") {
+            Some(Value::String(x)) => x.clone(),
+            Some(Value::Number(x)) => x.to_string(),
+            _ => return Err(Error::Invalid("missing `openapi` version".to_owned())),
+        };
+        if !version.starts_with("3.0") && !version.starts_with("3.1") {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        let doc = Document { root };
+        let reserved = match &self.client {
+            Some(client) => vec![type_name(client)],
+            None => Vec::new(),
+        };
+        let mut models = Models::new(doc, reserved);
+        models.define_components()?;
+
+        // An endpoint for every operation
+        let mut endpoints = Vec::new();
+        let paths = root
+            .get("paths")
+            .and_then(Value::as_mapping)
+            .into_iter()
+            .flatten();
+        for (path, item) in paths {
+            let Some(path) = path.as_str() else {
+                continue;
+            };
+            let item = doc.resolve(item)?;
+            for method in METHODS {
+                let Some(operation) = item.get(*method) else {
+                    continue;
+                };
+                endpoints.push(
+                    Operation {
+                        doc,
+                        path,
+                        method,
+                        item,
+                        operation,
+                    }
+                    .generate(&mut models)?,
+                );
+            }
+        }
+
+        let client = self.client.as_ref().map(|x| self.client_tokens(root, x));
+        let models = models.into_definitions();
+        Ok(quote! {
+            #client
+            #(#endpoints)*
+            #(#models)*
+        })
+    }
+
+    /// Generate the `RestClient` skeleton.
+    fn client_tokens(&self, root: &Value, client: &str) -> TokenStream {
+        let info = root.get("info");
+        let title = info.and_then(|x| string(x, "title")).unwrap_or("the API");
+        let docs = docs(&[
+            Some(&format!("A client for {title}.")),
+            info.and_then(|x| string(x, "description")),
+        ]);
+
+        let base = self
+            .base_url
+            .clone()
+            .or_else(|| {
+                root.get("servers")
+                    .and_then(Value::as_sequence)
+                    .and_then(|x| x.first())
+                    .and_then(|x| string(x, "url"))
+                    .map(str::to_owned)
+            })
+            .unwrap_or_else(|| "http://localhost/".to_owned());
+        let base = format!("{base:?}");
+
+        let client = ident(&type_name(client));
+        quote! {
+            #docs
+            ///
+            /// Requests are forwarded to the inner client, which also decides the error type.
+            #[derive(Debug, Clone)]
+            pub struct #client<C> {
+                inner: C,
+            }
+            impl<C> #client<C> {
+                /// Wrap `inner`.
+                pub fn new(inner: C) -> Self {
+                    Self { inner }
+                }
+
+                /// Get the inner client.
+                pub fn inner(&self) -> &C {
+                    &self.inner
+                }
+            }
+            #[::api_builder::api_rest_client(base = #base)]
+            impl<C> ::api_builder::RestClient for #client<C>
+            where
+                C: ::api_builder::RestClient,
+            {
+                type Error = C::Error;
+            }
+            impl<C> ::api_builder::Client for #client<C>
+            where
+                C: ::api_builder::Client,
+            {
+                fn rest(
+                    &self,
+                    request: ::api_builder::Request<Vec<u8>>,
+                ) -> Result<::api_builder::Response<::api_builder::Bytes>, ::api_builder::error::APIError<Self::Error>> {
+                    self.inner.rest(request)
+                }
+
+                fn rest_body(
+                    &self,
+                    request: ::api_builder::Request<::api_builder::RequestBody>,
+                ) -> Result<::api_builder::Response<::api_builder::Bytes>, ::api_builder::error::APIError<Self::Error>> {
+                    self.inner.rest_body(request)
+                }
+            }
+            impl<C> ::api_builder::AsyncClient for #client<C>
+            where
+                C: ::api_builder::AsyncClient + Sync,
+            {
+                async fn rest_async(
+                    &self,
+                    request: ::api_builder::Request<Vec<u8>>,
+                ) -> Result<::api_builder::Response<::api_builder::Bytes>, ::api_builder::error::APIError<Self::Error>> {
+                    self.inner.rest_async(request).await
+                }
+
+                async fn rest_body_async(
+                    &self,
+                    request: ::api_builder::Request<::api_builder::RequestBody>,
+                ) -> Result<::api_builder::Response<::api_builder::Bytes>, ::api_builder::error::APIError<Self::Error>> {
+                    self.inner.rest_body_async(request).await
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Where the generated code for the pet store is kept.
+    const SNAPSHOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/snapshots/petstore.rs");
+
+    /// Compares the generated code with the snapshot, which is rewritten when `UPDATE_SNAPSHOTS` is set.
+    #[test]
+    fn generates_petstore() {
+        let generated = Generator::new()
+            .client("PetStore")
+            .generate(include_str!("../tests/fixtures/petstore.yaml"))
+            .unwrap();
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::write(SNAPSHOT, &generated).unwrap();
+        }
+        let snapshot = std::fs::read_to_string(SNAPSHOT).unwrap_or_default();
+        assert!(
+            generated == snapshot,
+            "the generated code changed, run with `UPDATE_SNAPSHOTS=1` to accept it"
+        );
+    }
+
+    /// The snapshot must compile against `api_builder`.
+    #[allow(dead_code, deprecated)]
+    mod petstore {
+        include!("../tests/snapshots/petstore.rs");
+    }
+}
@@ -0,0 +1,66 @@
+use std::process::ExitCode;
+
+use api_builder_openapi::Generator;
+
+const USAGE: &str = "\
+Generates api_builder endpoints from an OpenAPI 3.0 or 3.1 document.
+
+Usage: api-builder-openapi [OPTIONS] <INPUT>
+
+Options:
+  -o, --output <FILE>  Write to FILE instead of stdout
+      --client <NAME>  The name of the client skeleton [default: Client]
+      --no-client      Don't generate the client skeleton
+      --base-url <URL> The base URL of the client skeleton [default: the first server]
+  -h, --help           Print this message";
+
+/// Parse the arguments and run the generator.
+fn run() -> Result<(), String> {
+    let mut generator = Generator::new();
+    let mut input = None;
+    let mut output = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("missing value for `{name}`"))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(());
+            }
+            "-o" | "--output" => output = Some(value(&arg)?),
+            "--client" => generator = generator.client(value(&arg)?),
+            "--no-client" => generator = generator.without_client(),
+            "--base-url" => generator = generator.base_url(value(&arg)?),
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{arg}`\n\n{USAGE}")),
+            _ if input.is_none() => input = Some(arg),
+            _ => return Err(format!("unexpected argument `{arg}`\n\n{USAGE}")),
+        }
+    }
+    let input = input.ok_or_else(|| format!("missing input\n\n{USAGE}"))?;
+
+    let document = std::fs::read_to_string(&input).map_err(|e| format!("{input}: {e}"))?;
+    let code = generator
+        .generate(&document)
+        .map_err(|e| format!("{input}: {e}"))?;
+    match output {
+        Some(output) => std::fs::write(&output, code).map_err(|e| format!("{output}: {e}")),
+        None => {
+            print!("{code}");
+            Ok(())
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
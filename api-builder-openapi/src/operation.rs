@@ -0,0 +1,561 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use serde_norway::Value;
+use syn::LitStr;
+
+use crate::{
+    Error,
+    naming::{field_name, ident, type_name, unique},
+    schema::{Document, Models, docs, sequence, string},
+};
+
+/// The methods an OpenAPI path item may have.
+pub(crate) const METHODS: &[&str] = &[
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// How a parameter value becomes a string.
+///
+/// Query parameters use `ToQueryValue` instead of `Display`.
+enum Format {
+    /// With `Display`.
+    Display,
+    /// Each item with `Display`, as separate values.
+    Each,
+    /// Each item with `Display`, separated by commas.
+    Joined,
+    /// As JSON.
+    Json,
+}
+
+/// A parameter of an operation.
+struct Parameter<'a> {
+    name: &'a str,
+    location: &'a str,
+    required: bool,
+    schema: Option<&'a Value>,
+    description: Option<&'a str>,
+    explode: bool,
+}
+
+/// A field of an endpoint struct which isn't the body.
+struct Field {
+    name: String,
+    location: String,
+    key: String,
+    required: bool,
+    format: Format,
+}
+
+/// The content type, schema and whether a request body is required.
+type RequestBody<'a> = (&'a str, Option<&'a Value>, bool);
+
+/// The body of an endpoint.
+struct Body {
+    field: String,
+    content_type: String,
+    json: bool,
+    required: bool,
+}
+
+/// Generates an endpoint struct for an operation.
+pub(crate) struct Operation<'a> {
+    pub doc: Document<'a>,
+    pub path: &'a str,
+    pub method: &'a str,
+    pub item: &'a Value,
+    pub operation: &'a Value,
+}
+impl<'a> Operation<'a> {
+    /// The struct name for the operation.
+    pub fn name(&self) -> String {
+        match string(self.operation, "operationId") {
+            Some(id) => type_name(id),
+            None => type_name(&format!("{} {}", self.method, self.path)),
+        }
+    }
+
+    /// The parameters of the path item and the operation, where the operation takes priority.
+    fn parameters(&self) -> Result<Vec<Parameter<'a>>, Error> {
+        let mut parameters: Vec<Parameter<'a>> = Vec::new();
+        let all = sequence(self.item, "parameters")
+            .iter()
+            .chain(sequence(self.operation, "parameters"));
+        for parameter in all {
+            let parameter = self.doc.resolve(parameter)?;
+            let (Some(name), Some(location)) = (string(parameter, "name"), string(parameter, "in"))
+            else {
+                return Err(Error::Invalid(format!(
+                    "a parameter of {} {} is missing `name` or `in`",
+                    self.method, self.path
+                )));
+            };
+            let required = location == "path"
+                || parameter.get("required").and_then(Value::as_bool) == Some(true);
+            let schema = parameter.get("schema").or_else(|| {
+                parameter
+                    .get("content")
+                    .and_then(Value::as_mapping)
+                    .and_then(|x| x.values().next())
+                    .and_then(|x| x.get("schema"))
+            });
+
+            parameters.retain(|x| !(x.name == name && x.location == location));
+            parameters.push(Parameter {
+                name,
+                location,
+                required,
+                schema,
+                description: string(parameter, "description"),
+                explode: parameter.get("explode").and_then(Value::as_bool).unwrap_or(
+                    matches!(string(parameter, "style"), None | Some("form"))
+                        && location != "header",
+                ),
+            });
+        }
+        Ok(parameters)
+    }
+
+    /// How to turn the value of a parameter into a string.
+    fn format(&self, schema: Option<&Value>, explode: bool) -> Result<Format, Error> {
+        let Some(schema) = schema else {
+            return Ok(Format::Display);
+        };
+        let schema = self.doc.resolve(schema)?;
+        let display = |schema: &Value| {
+            schema.get("enum").is_some()
+                || matches!(
+                    string(schema, "type"),
+                    Some("string" | "integer" | "number" | "boolean")
+                )
+        };
+        if display(schema) {
+            return Ok(Format::Display);
+        }
+        if string(schema, "type") == Some("array")
+            && let Some(items) = schema.get("items")
+            && display(self.doc.resolve(items)?)
+        {
+            return Ok(if explode {
+                Format::Each
+            } else {
+                Format::Joined
+            });
+        }
+        Ok(Format::Json)
+    }
+
+    /// The request body, as its content type, schema and whether it is required.
+    fn request_body(&self) -> Result<Option<RequestBody<'a>>, Error> {
+        let Some(body) = self.operation.get("requestBody") else {
+            return Ok(None);
+        };
+        let body = self.doc.resolve(body)?;
+        let required = body.get("required").and_then(Value::as_bool) == Some(true);
+        let Some(content) = body.get("content").and_then(Value::as_mapping) else {
+            return Ok(None);
+        };
+
+        let mut content = content
+            .iter()
+            .filter_map(|(key, value)| Some((key.as_str()?, value)))
+            .collect::<Vec<_>>();
+        content.sort_by_key(|(content_type, _)| !is_json(content_type));
+        Ok(content
+            .first()
+            .map(|(content_type, media)| (*content_type, media.get("schema"), required)))
+    }
+
+    /// The schema of the first successful JSON response.
+    fn response(&self) -> Option<&'a Value> {
+        let responses = self.operation.get("responses")?.as_mapping()?;
+        let mut success = responses
+            .iter()
+            .filter_map(|(status, response)| {
+                let status = match status {
+                    Value::Number(x) => x.to_string(),
+                    Value::String(x) => x.clone(),
+                    _ => return None,
+                };
+                status.starts_with('2').then_some((status, response))
+            })
+            .collect::<Vec<_>>();
+        success.sort_by(|a, b| a.0.cmp(&b.0));
+
+        success.into_iter().find_map(|(_, response)| {
+            let response = self.doc.resolve(response).ok()?;
+            response
+                .get("content")?
+                .as_mapping()?
+                .iter()
+                .find(|(key, _)| key.as_str().is_some_and(is_json))?
+                .1
+                .get("schema")
+        })
+    }
+
+    /// Generate the endpoint struct, its `Endpoint` impl and its response type.
+    pub fn generate(&self, models: &mut Models<'a>) -> Result<TokenStream, Error> {
+        let name = unique(self.name(), &mut models.names);
+        let struct_name = ident(&name);
+
+        // Fields for every parameter
+        let mut taken = Vec::new();
+        let mut fields = Vec::new();
+        let mut definitions = Vec::new();
+        for parameter in self.parameters()? {
+            if parameter.location == "cookie" {
+                continue;
+            }
+            if parameter.location == "header"
+                && !parameter
+                    .name
+                    .bytes()
+                    .all(|x| x.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&x))
+            {
+                return Err(Error::Invalid(format!(
+                    "invalid header name `{}` in {} {}",
+                    parameter.name, self.method, self.path
+                )));
+            }
+
+            let field = unique(field_name(parameter.name), &mut taken);
+            let ty = match parameter.schema {
+                Some(schema) => {
+                    models
+                        .rust_type_parts(schema, &format!("{name} {}", parameter.name))?
+                        .0
+                }
+                None => quote!(String),
+            };
+            let ty = match parameter.required {
+                true => ty,
+                false => quote!(Option<#ty>),
+            };
+            let docs = docs(&[parameter.description]);
+            let field_ident = ident(&field);
+            definitions.push(quote! {
+                #docs
+                pub #field_ident: #ty,
+            });
+            fields.push(Field {
+                name: field,
+                location: parameter.location.to_owned(),
+                key: parameter.name.to_owned(),
+                required: parameter.required,
+                format: self.format(parameter.schema, parameter.explode)?,
+            });
+        }
+
+        // A field for the body
+        let body = match self.request_body()? {
+            Some((content_type, schema, required)) => {
+                let json = is_json(content_type);
+                let ty = match (json, schema) {
+                    (true, Some(schema)) => models.rust_type(schema, &format!("{name} body"))?,
+                    (true, None) => quote!(::api_builder::serde_json::Value),
+                    (false, _) => quote!(Vec<u8>),
+                };
+                let ty = match required {
+                    true => ty,
+                    false => quote!(Option<#ty>),
+                };
+                let field = unique("body".to_owned(), &mut taken);
+                let field_ident = ident(&field);
+                definitions.push(quote! {
+                    /// The request body.
+                    pub #field_ident: #ty,
+                });
+                Some(Body {
+                    field,
+                    content_type: content_type.to_owned(),
+                    json,
+                    required,
+                })
+            }
+            None => None,
+        };
+
+        // The response, always named so it can be given to `api_endpoint`
+        let mut response_arg = None;
+        if let Some(schema) = self.response() {
+            let response = format!("{name}Response");
+            let ty = models.rust_type(schema, &response)?;
+            if ty.to_string() != response {
+                let docs = format!(" The response of [`{name}`].");
+                let response = ident(&unique(response, &mut models.names));
+                models.push(quote! {
+                    #[doc = #docs]
+                    pub type #response = #ty;
+                });
+                response_arg = Some(quote!(, response = #response));
+            } else {
+                response_arg = Some(quote!(, response = #ty));
+            }
+        }
+
+        let docs = docs(&[
+            string(self.operation, "summary"),
+            string(self.operation, "description"),
+            Some(&format!("`{} {}`", self.method.to_uppercase(), self.path)),
+        ]);
+        let deprecated = self.operation.get("deprecated").and_then(Value::as_bool) == Some(true);
+        let (deprecated, allow_deprecated) = match deprecated {
+            true => (
+                Some(quote!(#[deprecated])),
+                Some(quote!(#[allow(deprecated)])),
+            ),
+            false => (None, None),
+        };
+        let method = ident(&self.method.to_uppercase());
+        let path = self.path_expr(&fields)?;
+        let target = self.target_fn(&fields)?;
+        let query_params = query_params(&fields);
+        let headers = headers(&fields);
+        let body = body.map(|x| body_fn(&x));
+
+        Ok(quote! {
+            #docs
+            #deprecated
+            #[derive(Debug, Clone)]
+            pub struct #struct_name {
+                #(#definitions)*
+            }
+            #allow_deprecated
+            #[::api_builder::api_endpoint(method = #method, path = #path #response_arg)]
+            impl ::api_builder::Endpoint for #struct_name {
+                #target
+                #query_params
+                #headers
+                #body
+            }
+        })
+    }
+
+    /// The expression for the path, relative to the base URL.
+    ///
+    /// The path parameters are not escaped here, requests use `target` instead.
+    fn path_expr(&self, fields: &[Field]) -> Result<LitStr, Error> {
+        let path = self.path.trim_start_matches('/');
+        let mut template = String::new();
+        let mut arguments = Vec::new();
+        let mut rest = path;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..].find('}').ok_or_else(|| {
+                Error::Invalid(format!("unclosed path parameter in {}", self.path))
+            })? + start;
+            template.push_str(&rest[..start].replace('}', "}}"));
+            template.push_str("{}");
+
+            let key = &rest[start + 1..end];
+            let field = fields
+                .iter()
+                .find(|x| x.location == "path" && x.key == key)
+                .ok_or_else(|| {
+                    Error::Invalid(format!("missing path parameter `{key}` in {}", self.path))
+                })?;
+            arguments.push(format!("self.{}", ident(&field.name)));
+            rest = &rest[end + 1..];
+        }
+        template.push_str(&rest.replace('}', "}}"));
+
+        let expr = match arguments.is_empty() {
+            true => format!("{template:?}"),
+            false => format!("format!({template:?}, {})", arguments.join(", ")),
+        };
+        Ok(LitStr::new(&expr, proc_macro2::Span::call_site()))
+    }
+
+    /// The `target` method, which escapes each path parameter as a whole segment.
+    fn target_fn(&self, fields: &[Field]) -> Result<Option<TokenStream>, Error> {
+        if !self.path.contains('{') {
+            return Ok(None);
+        }
+
+        let mut segments = Vec::new();
+        for segment in self.path.split('/').filter(|x| !x.is_empty()) {
+            let mut template = String::new();
+            let mut arguments = Vec::new();
+            let mut rest = segment;
+            while let Some(start) = rest.find('{') {
+                let end = rest[start..].find('}').ok_or_else(|| {
+                    Error::Invalid(format!("unclosed path parameter in {}", self.path))
+                })? + start;
+                template.push_str(&rest[..start].replace('}', "}}"));
+                template.push_str("{}");
+
+                let key = &rest[start + 1..end];
+                let field = fields
+                    .iter()
+                    .find(|x| x.location == "path" && x.key == key)
+                    .ok_or_else(|| {
+                        Error::Invalid(format!("missing path parameter `{key}` in {}", self.path))
+                    })?;
+                let field = ident(&field.name);
+                arguments.push(quote!(self.#field));
+                rest = &rest[end + 1..];
+            }
+            template.push_str(&rest.replace('}', "}}"));
+
+            segments.push(match arguments.as_slice() {
+                [] => quote!(.segment(#segment)),
+                [argument] if template == "{}" => {
+                    quote!(.segment(::std::string::ToString::to_string(&#argument)))
+                }
+                _ => quote!(.segment(format!(#template, #(#arguments),*))),
+            });
+        }
+        if self.path.ends_with('/') {
+            segments.push(quote!(.path("/")));
+        }
+
+        Ok(Some(quote! {
+            fn target(&self) -> ::api_builder::RequestTarget<'_> {
+                ::api_builder::RequestTarget::new()
+                    #(#segments)*
+                    .query(::api_builder::Endpoint::query_params(self))
+            }
+        }))
+    }
+}
+
+/// Whether a content type is JSON.
+fn is_json(content_type: &str) -> bool {
+    let content_type = content_type.split(';').next().unwrap_or_default().trim();
+    content_type == "application/json" || content_type.ends_with("+json")
+}
+
+/// Call `push` with each value of a field.
+///
+/// When `typed`, the values are passed as they are for `ToQueryValue`, otherwise they are strings.
+fn for_each_value(
+    field: &Field,
+    typed: bool,
+    push: impl Fn(TokenStream) -> TokenStream,
+) -> TokenStream {
+    let name = ident(&field.name);
+    let (value, reference) = match field.required {
+        true => (quote!(self.#name), quote!(&self.#name)),
+        false => (quote!(value), quote!(value)),
+    };
+    let push = match (&field.format, typed) {
+        (Format::Display, true) => push(reference.clone()),
+        (Format::Display, false) => push(quote!(#value.to_string())),
+        (Format::Each, _) => {
+            let push = match typed {
+                true => push(quote!(item)),
+                false => push(quote!(item.to_string())),
+            };
+            quote! {
+                for item in #reference {
+                    #push
+                }
+            }
+        }
+        (Format::Joined, true) => push(quote! {
+            #value
+                .iter()
+                .filter_map(::api_builder::ToQueryValue::to_query_value)
+                .collect::<Vec<_>>()
+                .join(",")
+        }),
+        (Format::Joined, false) => push(quote! {
+            #value.iter().map(ToString::to_string).collect::<Vec<_>>().join(",")
+        }),
+        // The generated types derive `Serialize` with string keys, so serializing them can't fail,
+        // which matters for query parameters as `query_params` has no error to return
+        (Format::Json, true) => push(quote! {
+            ::api_builder::serde_json::to_string(#reference).ok()
+        }),
+        (Format::Json, false) => push(quote! {
+            ::api_builder::serde_json::to_string(#reference)
+                .map_err(|e| ::api_builder::error::HeaderError::Other(e.into()))?
+        }),
+    };
+    match field.required {
+        true => push,
+        false => quote! {
+            if let Some(value) = &self.#name {
+                #push
+            }
+        },
+    }
+}
+
+/// The `query_params` method, if there are any query parameters.
+fn query_params(fields: &[Field]) -> Option<TokenStream> {
+    let pushes = fields
+        .iter()
+        .filter(|x| x.location == "query")
+        .map(|field| {
+            let key = &field.key;
+            for_each_value(field, true, |value| quote!(params.push((#key, #value));))
+        })
+        .collect::<Vec<_>>();
+    if pushes.is_empty() {
+        return None;
+    }
+
+    Some(quote! {
+        fn query_params(&self) -> Option<::api_builder::QueryParamPairs<'_>> {
+            let mut params = ::api_builder::QueryParamPairs::default();
+            #(#pushes)*
+            Some(params)
+        }
+    })
+}
+
+/// The `headers` method, if there are any header parameters.
+fn headers(fields: &[Field]) -> Option<TokenStream> {
+    let inserts = fields
+        .iter()
+        .filter(|x| x.location == "header")
+        .map(|field| {
+            let key = field.key.to_lowercase();
+            for_each_value(
+                field,
+                false,
+                |value| quote!(headers.append(#key, #value.parse()?);),
+            )
+        })
+        .collect::<Vec<_>>();
+    if inserts.is_empty() {
+        return None;
+    }
+
+    Some(quote! {
+        fn headers(&self) -> Result<Option<::api_builder::HeaderMap>, ::api_builder::error::HeaderError> {
+            let mut headers = ::api_builder::HeaderMap::new();
+            #(#inserts)*
+            Ok(Some(headers))
+        }
+    })
+}
+
+/// The `body` method.
+fn body_fn(body: &Body) -> TokenStream {
+    let field = ident(&body.field);
+    let content_type = &body.content_type;
+    let encode = match body.json {
+        true => quote!(::api_builder::serde_json::to_vec(body)?),
+        false => quote!(body.clone()),
+    };
+    let body = match body.required {
+        true => quote! {
+            let body = &self.#field;
+            Ok(Some((#content_type.into(), #encode)))
+        },
+        false => quote! {
+            match &self.#field {
+                Some(body) => Ok(Some((#content_type.into(), #encode))),
+                None => Ok(None),
+            }
+        },
+    };
+
+    quote! {
+        fn body(&self) -> Result<Option<(::std::borrow::Cow<'static, str>, Vec<u8>)>, ::api_builder::error::BodyError> {
+            #body
+        }
+    }
+}
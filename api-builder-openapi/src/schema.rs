@@ -0,0 +1,543 @@
+use std::collections::HashSet;
+
+use indexmap::IndexMap;
+use proc_macro2::TokenStream;
+use quote::quote;
+use serde_norway::Value;
+
+use crate::{
+    Error,
+    naming::{field_name, ident, type_name, unique},
+};
+
+/// The prefix of references to schema components.
+const SCHEMA_PREFIX: &str = "#/components/schemas/";
+
+/// An OpenAPI document, with helpers for following references.
+#[derive(Clone, Copy)]
+pub(crate) struct Document<'a> {
+    pub root: &'a Value,
+}
+impl<'a> Document<'a> {
+    /// Follow a local reference such as `#/components/parameters/limit`.
+    pub fn pointer(&self, reference: &str) -> Result<&'a Value, Error> {
+        let path = reference
+            .strip_prefix("#/")
+            .ok_or_else(|| Error::UnresolvedReference(reference.to_owned()))?;
+        path.split('/')
+            .try_fold(self.root, |value, segment| {
+                value.get(segment.replace("~1", "/").replace("~0", "~"))
+            })
+            .ok_or_else(|| Error::UnresolvedReference(reference.to_owned()))
+    }
+
+    /// Follow `$ref` until reaching a value which isn't a reference.
+    pub fn resolve(&self, mut value: &'a Value) -> Result<&'a Value, Error> {
+        for _ in 0..32 {
+            match value.get("$ref").and_then(Value::as_str) {
+                Some(reference) => value = self.pointer(reference)?,
+                None => return Ok(value),
+            }
+        }
+        Err(Error::Invalid("too many nested references".to_owned()))
+    }
+}
+
+/// Get a string from a value.
+pub(crate) fn string<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    value.get(key).and_then(Value::as_str)
+}
+
+/// Get a sequence from a value, empty if it is missing.
+pub(crate) fn sequence<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value
+        .get(key)
+        .and_then(Value::as_sequence)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+/// Turn the title and description into doc attributes.
+pub(crate) fn docs(lines: &[Option<&str>]) -> TokenStream {
+    let lines = lines
+        .iter()
+        .flatten()
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let lines = lines.lines().map(|line| format!(" {line}"));
+    quote! { #(#[doc = #lines])* }
+}
+
+/// How a schema is represented in Rust.
+enum Kind<'a> {
+    /// A struct with named fields.
+    Struct,
+    /// A unit enum of strings.
+    Enum(Vec<&'a str>),
+    /// An untagged enum of other types.
+    Union(Vec<&'a Value>),
+    /// A type which doesn't need a definition, e.g. `String` or `Vec<T>`.
+    Inline,
+}
+
+/// Whether the schema only allows `null`.
+fn is_null(schema: &Value) -> bool {
+    string(schema, "type") == Some("null")
+}
+
+/// The non-null `type`s of a schema, and whether it is nullable.
+fn types(schema: &Value) -> (Vec<&str>, bool) {
+    let mut nullable = schema.get("nullable").and_then(Value::as_bool) == Some(true);
+    let types = match schema.get("type") {
+        Some(Value::String(x)) => vec![x.as_str()],
+        Some(Value::Sequence(x)) => x.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    nullable |= types.contains(&"null");
+    let types = types.into_iter().filter(|x| *x != "null").collect();
+    (types, nullable)
+}
+
+/// The `oneOf` or `anyOf` variants of a schema, without `null`, and whether one was `null`.
+fn variants(schema: &Value) -> Option<(Vec<&Value>, bool)> {
+    let variants = schema
+        .get("oneOf")
+        .or_else(|| schema.get("anyOf"))?
+        .as_sequence()?;
+    let non_null = variants.iter().filter(|x| !is_null(x)).collect::<Vec<_>>();
+    let nullable = non_null.len() != variants.len();
+    Some((non_null, nullable))
+}
+
+/// Decide how to represent a schema which isn't a reference.
+fn kind(schema: &Value) -> Kind<'_> {
+    if let Some((variants, _)) = variants(schema)
+        && variants.len() > 1
+    {
+        return Kind::Union(variants);
+    }
+
+    let all_of = sequence(schema, "allOf");
+    if all_of.len() > 1 || (!all_of.is_empty() && schema.get("properties").is_some()) {
+        return Kind::Struct;
+    }
+
+    if let Some(values) = schema.get("enum").and_then(Value::as_sequence) {
+        let values = values.iter().filter(|x| !x.is_null()).collect::<Vec<_>>();
+        if !values.is_empty() && values.iter().all(|x| x.is_string()) {
+            return Kind::Enum(values.iter().filter_map(|x| x.as_str()).collect());
+        }
+    }
+
+    let has_properties = schema
+        .get("properties")
+        .and_then(Value::as_mapping)
+        .is_some_and(|x| !x.is_empty());
+    let (types, _) = types(schema);
+    if has_properties && (types.is_empty() || types == ["object"]) {
+        return Kind::Struct;
+    }
+
+    Kind::Inline
+}
+
+/// Generates Rust types for schemas.
+pub(crate) struct Models<'a> {
+    doc: Document<'a>,
+    /// Every type name which has been used.
+    pub names: Vec<String>,
+    /// The type name for each schema component.
+    components: IndexMap<String, String>,
+    /// Every type definition, in order.
+    definitions: Vec<TokenStream>,
+}
+impl<'a> Models<'a> {
+    /// Reserve names for every schema component.
+    pub fn new(doc: Document<'a>, reserved: Vec<String>) -> Self {
+        let mut names = reserved;
+        let components = doc
+            .root
+            .get("components")
+            .and_then(|x| x.get("schemas"))
+            .and_then(Value::as_mapping)
+            .into_iter()
+            .flatten()
+            .filter_map(|(key, _)| key.as_str())
+            .map(|key| (key.to_owned(), unique(type_name(key), &mut names)))
+            .collect();
+
+        Self {
+            doc,
+            names,
+            components,
+            definitions: Vec::new(),
+        }
+    }
+
+    /// Define every schema component.
+    pub fn define_components(&mut self) -> Result<(), Error> {
+        let components = self.components.clone();
+        for (key, name) in components {
+            let schema = self.doc.pointer(&format!("{SCHEMA_PREFIX}{key}"))?;
+            self.define(&name, schema)?;
+        }
+        Ok(())
+    }
+
+    /// Take every definition.
+    pub fn into_definitions(self) -> Vec<TokenStream> {
+        self.definitions
+    }
+
+    /// Add a definition.
+    pub fn push(&mut self, definition: TokenStream) {
+        self.definitions.push(definition);
+    }
+
+    /// The type name of a reference to a schema component.
+    fn component(&self, reference: &str) -> Option<&str> {
+        let key = reference.strip_prefix(SCHEMA_PREFIX)?;
+        self.components.get(key).map(String::as_str)
+    }
+
+    /// The Rust type for a schema, defining any inline types with a name based on `hint`.
+    pub fn rust_type(&mut self, schema: &'a Value, hint: &str) -> Result<TokenStream, Error> {
+        let (ty, nullable) = self.rust_type_parts(schema, hint)?;
+        Ok(if nullable { quote!(Option<#ty>) } else { ty })
+    }
+
+    /// The Rust type for a schema without `Option`, and whether it is nullable.
+    pub fn rust_type_parts(
+        &mut self,
+        schema: &'a Value,
+        hint: &str,
+    ) -> Result<(TokenStream, bool), Error> {
+        if let Some(reference) = string(schema, "$ref") {
+            if let Some(name) = self.component(reference) {
+                let name = ident(name);
+                return Ok((quote!(#name), false));
+            }
+            let schema = self.doc.pointer(reference)?;
+            return self.rust_type_parts(schema, hint);
+        }
+
+        let (types, mut nullable) = types(schema);
+        let ty = match kind(schema) {
+            Kind::Struct | Kind::Enum(_) | Kind::Union(_) => {
+                let name = unique(type_name(hint), &mut self.names);
+                self.define(&name, schema)?;
+                let name = ident(&name);
+                quote!(#name)
+            }
+            Kind::Inline => {
+                if let Some((variants, variant_nullable)) = variants(schema)
+                    && let [variant] = variants[..]
+                {
+                    nullable |= variant_nullable;
+                    let (ty, variant_nullable) = self.rust_type_parts(variant, hint)?;
+                    return Ok((ty, nullable || variant_nullable));
+                }
+                if let [schema] = sequence(schema, "allOf") {
+                    let (ty, all_of_nullable) = self.rust_type_parts(schema, hint)?;
+                    return Ok((ty, nullable || all_of_nullable));
+                }
+                self.primitive(schema, &types, hint)?
+            }
+        };
+        Ok((ty, nullable))
+    }
+
+    /// The Rust type for a schema without a definition.
+    fn primitive(
+        &mut self,
+        schema: &'a Value,
+        types: &[&str],
+        hint: &str,
+    ) -> Result<TokenStream, Error> {
+        let inferred = if schema.get("items").is_some() {
+            "array"
+        } else if schema.get("additionalProperties").is_some() {
+            "object"
+        } else {
+            ""
+        };
+        let ty = match types {
+            [ty] => *ty,
+            [] => inferred,
+            _ => "",
+        };
+        let format = string(schema, "format");
+
+        Ok(match ty {
+            "string" => quote!(String),
+            "boolean" => quote!(bool),
+            "integer" => match format {
+                Some("int8") => quote!(i8),
+                Some("int16") => quote!(i16),
+                Some("int32") => quote!(i32),
+                Some("uint8") => quote!(u8),
+                Some("uint16") => quote!(u16),
+                Some("uint32") => quote!(u32),
+                Some("uint64") => quote!(u64),
+                _ => quote!(i64),
+            },
+            "number" => match format {
+                Some("float") => quote!(f32),
+                _ => quote!(f64),
+            },
+            "array" => match schema.get("items") {
+                Some(items) => {
+                    let items = self.rust_type(items, &format!("{hint} item"))?;
+                    quote!(Vec<#items>)
+                }
+                None => quote!(Vec<::api_builder::serde_json::Value>),
+            },
+            "object" => match schema.get("additionalProperties") {
+                Some(values) if values.is_mapping() => {
+                    let values = self.rust_type(values, &format!("{hint} value"))?;
+                    quote!(::std::collections::HashMap<String, #values>)
+                }
+                _ => {
+                    quote!(::api_builder::serde_json::Map<String, ::api_builder::serde_json::Value>)
+                }
+            },
+            _ => quote!(::api_builder::serde_json::Value),
+        })
+    }
+
+    /// Define a type named `name` for a schema.
+    fn define(&mut self, name: &str, schema: &'a Value) -> Result<(), Error> {
+        let docs = docs(&[string(schema, "title"), string(schema, "description")]);
+        let definition = match kind(schema) {
+            Kind::Struct => self.define_struct(name, schema)?,
+            Kind::Enum(values) => define_enum(name, &values),
+            Kind::Union(variants) => self.define_union(name, &variants)?,
+            Kind::Inline => {
+                let ty = self.rust_type(schema, &format!("{name} inner"))?;
+                let name = ident(name);
+                quote! { pub type #name = #ty; }
+            }
+        };
+        self.definitions.push(quote! {
+            #docs
+            #definition
+        });
+        Ok(())
+    }
+
+    /// Collect the properties and required properties of a schema, including `allOf`.
+    fn properties(
+        &self,
+        schema: &'a Value,
+        properties: &mut Vec<(&'a str, &'a Value)>,
+        required: &mut HashSet<&'a str>,
+        additional: &mut Option<&'a Value>,
+    ) -> Result<(), Error> {
+        let schema = self.doc.resolve(schema)?;
+        for part in sequence(schema, "allOf") {
+            self.properties(part, properties, required, additional)?;
+        }
+        for (key, value) in schema
+            .get("properties")
+            .and_then(Value::as_mapping)
+            .into_iter()
+            .flatten()
+        {
+            if let Some(key) = key.as_str() {
+                properties.retain(|(x, _)| *x != key);
+                properties.push((key, value));
+            }
+        }
+        required.extend(
+            sequence(schema, "required")
+                .iter()
+                .filter_map(Value::as_str),
+        );
+        match schema.get("additionalProperties") {
+            Some(Value::Bool(false)) | None => {}
+            Some(value) => *additional = Some(value),
+        }
+        Ok(())
+    }
+
+    /// Define a struct with a field per property.
+    fn define_struct(&mut self, name: &str, schema: &'a Value) -> Result<TokenStream, Error> {
+        let mut properties = Vec::new();
+        let mut required = HashSet::new();
+        let mut additional = None;
+        self.properties(schema, &mut properties, &mut required, &mut additional)?;
+
+        let mut taken = Vec::new();
+        let mut fields = Vec::new();
+        for (key, property) in properties {
+            let field = unique(field_name(key), &mut taken);
+            let (mut ty, nullable) = self.rust_type_parts(property, &format!("{name} {key}"))?;
+            if self.is_recursive(property, name)? {
+                ty = quote!(Box<#ty>);
+            }
+
+            let docs = docs(&[string(property, "description")]);
+            let rename = (field != key).then(|| quote!(#[serde(rename = #key)]));
+            let (ty, default) = match (required.contains(key), nullable) {
+                (true, false) => (ty, None),
+                (true, true) => (quote!(Option<#ty>), None),
+                (false, _) => (
+                    quote!(Option<#ty>),
+                    Some(quote!(#[serde(default, skip_serializing_if = "Option::is_none")])),
+                ),
+            };
+            let field = ident(&field);
+            fields.push(quote! {
+                #docs
+                #rename
+                #default
+                pub #field: #ty,
+            });
+        }
+        if let Some(additional) = additional {
+            let field = ident(&unique("additional_properties".to_owned(), &mut taken));
+            let values = match additional {
+                Value::Mapping(_) => self.rust_type(additional, &format!("{name} value"))?,
+                _ => quote!(::api_builder::serde_json::Value),
+            };
+            fields.push(quote! {
+                #[serde(flatten)]
+                pub #field: ::std::collections::HashMap<String, #values>,
+            });
+        }
+
+        let name = ident(name);
+        Ok(quote! {
+            #[derive(Debug, Clone, PartialEq, ::serde::Serialize, ::serde::Deserialize)]
+            pub struct #name {
+                #(#fields)*
+            }
+        })
+    }
+
+    /// Define an untagged enum with a variant per schema.
+    fn define_union(&mut self, name: &str, schemas: &[&'a Value]) -> Result<TokenStream, Error> {
+        let mut taken = Vec::new();
+        let mut variants = Vec::new();
+        for (i, schema) in schemas.iter().enumerate() {
+            let variant = string(schema, "$ref")
+                .and_then(|x| self.component(x))
+                .map(type_name)
+                .or_else(|| string(schema, "title").map(type_name))
+                .unwrap_or_else(|| format!("Variant{i}"));
+            let variant = unique(variant, &mut taken);
+            let ty = self.rust_type(schema, &format!("{name} {variant}"))?;
+            let variant = ident(&variant);
+            variants.push(quote!(#variant(#ty),));
+        }
+
+        let name = ident(name);
+        Ok(quote! {
+            #[derive(Debug, Clone, PartialEq, ::serde::Serialize, ::serde::Deserialize)]
+            #[serde(untagged)]
+            pub enum #name {
+                #(#variants)*
+            }
+        })
+    }
+
+    /// Whether a property refers back to the struct `name` without indirection, so needs to be boxed.
+    fn is_recursive(&self, property: &Value, name: &str) -> Result<bool, Error> {
+        let mut seen = HashSet::new();
+        let mut stack = self.direct_components(property)?;
+        while let Some(component) = stack.pop() {
+            if component == name {
+                return Ok(true);
+            }
+            if seen.insert(component.clone()) {
+                let key = self
+                    .components
+                    .iter()
+                    .find(|(_, x)| **x == component)
+                    .map(|(key, _)| key.clone());
+                if let Some(key) = key {
+                    let schema = self.doc.pointer(&format!("{SCHEMA_PREFIX}{key}"))?;
+                    for (_, property) in self.struct_properties(schema)? {
+                        stack.extend(self.direct_components(property)?);
+                    }
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// The properties of a schema if it is a struct.
+    fn struct_properties(&self, schema: &'a Value) -> Result<Vec<(&'a str, &'a Value)>, Error> {
+        let mut properties = Vec::new();
+        if matches!(kind(schema), Kind::Struct) {
+            self.properties(schema, &mut properties, &mut HashSet::new(), &mut None)?;
+        }
+        Ok(properties)
+    }
+
+    /// The schema components a schema contains without a `Vec` or `HashMap` in between.
+    fn direct_components(&self, schema: &Value) -> Result<Vec<String>, Error> {
+        if let Some(reference) = string(schema, "$ref") {
+            return Ok(self
+                .component(reference)
+                .map(str::to_owned)
+                .into_iter()
+                .collect());
+        }
+        let mut components = Vec::new();
+        let nested = ["allOf", "oneOf", "anyOf"]
+            .into_iter()
+            .flat_map(|key| sequence(schema, key));
+        for schema in nested {
+            components.extend(self.direct_components(schema)?);
+        }
+        Ok(components)
+    }
+}
+
+/// Define a unit enum with a variant per string.
+fn define_enum(name: &str, values: &[&str]) -> TokenStream {
+    let mut taken = Vec::new();
+    let variants = values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let variant = match type_name(value).as_str() {
+                "Unnamed" => format!("Variant{i}"),
+                x => x.to_owned(),
+            };
+            ident(&unique(variant, &mut taken))
+        })
+        .collect::<Vec<_>>();
+
+    let name = ident(name);
+    quote! {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ::serde::Serialize, ::serde::Deserialize)]
+        pub enum #name {
+            #(
+                #[serde(rename = #values)]
+                #variants,
+            )*
+        }
+        impl #name {
+            /// The value of the variant.
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    #(Self::#variants => #values,)*
+                }
+            }
+        }
+        impl ::std::fmt::Display for #name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+        impl ::api_builder::ToQueryValue for #name {
+            fn to_query_value(&self) -> Option<::std::borrow::Cow<'static, str>> {
+                Some(::std::borrow::Cow::Borrowed(self.as_str()))
+            }
+        }
+    }
+}
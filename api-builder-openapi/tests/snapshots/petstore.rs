@@ -0,0 +1,246 @@
+/// A client for Pet Store.
+///
+/// A small pet store.
+///
+/// Requests are forwarded to the inner client, which also decides the error type.
+#[derive(Debug, Clone)]
+pub struct PetStore<C> {
+    inner: C,
+}
+impl<C> PetStore<C> {
+    /// Wrap `inner`.
+    pub fn new(inner: C) -> Self {
+        Self { inner }
+    }
+    /// Get the inner client.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+#[::api_builder::api_rest_client(base = "\"https://example.com/v1\"")]
+impl<C> ::api_builder::RestClient for PetStore<C>
+where
+    C: ::api_builder::RestClient,
+{
+    type Error = C::Error;
+}
+impl<C> ::api_builder::Client for PetStore<C>
+where
+    C: ::api_builder::Client,
+{
+    fn rest(
+        &self,
+        request: ::api_builder::Request<Vec<u8>>,
+    ) -> Result<
+        ::api_builder::Response<::api_builder::Bytes>,
+        ::api_builder::error::APIError<Self::Error>,
+    > {
+        self.inner.rest(request)
+    }
+    fn rest_body(
+        &self,
+        request: ::api_builder::Request<::api_builder::RequestBody>,
+    ) -> Result<
+        ::api_builder::Response<::api_builder::Bytes>,
+        ::api_builder::error::APIError<Self::Error>,
+    > {
+        self.inner.rest_body(request)
+    }
+}
+impl<C> ::api_builder::AsyncClient for PetStore<C>
+where
+    C: ::api_builder::AsyncClient + Sync,
+{
+    async fn rest_async(
+        &self,
+        request: ::api_builder::Request<Vec<u8>>,
+    ) -> Result<
+        ::api_builder::Response<::api_builder::Bytes>,
+        ::api_builder::error::APIError<Self::Error>,
+    > {
+        self.inner.rest_async(request).await
+    }
+    async fn rest_body_async(
+        &self,
+        request: ::api_builder::Request<::api_builder::RequestBody>,
+    ) -> Result<
+        ::api_builder::Response<::api_builder::Bytes>,
+        ::api_builder::error::APIError<Self::Error>,
+    > {
+        self.inner.rest_body_async(request).await
+    }
+}
+/// List the pets.
+///
+/// `GET /pets`
+#[derive(Debug, Clone)]
+pub struct ListPets {
+    pub limit: Option<i32>,
+    pub tags: Option<Vec<String>>,
+    pub filter: Option<Filter>,
+    pub x_request_id: String,
+}
+#[::api_builder::api_endpoint(
+    method = GET,
+    path = "\"pets\"",
+    response = ListPetsResponse
+)]
+impl ::api_builder::Endpoint for ListPets {
+    fn query_params(&self) -> Option<::api_builder::QueryParamPairs<'_>> {
+        let mut params = ::api_builder::QueryParamPairs::default();
+        if let Some(value) = &self.limit {
+            params.push(("limit", value));
+        }
+        if let Some(value) = &self.tags {
+            for item in value {
+                params.push(("tags", item));
+            }
+        }
+        if let Some(value) = &self.filter {
+            params.push(("filter", ::api_builder::serde_json::to_string(value).ok()));
+        }
+        Some(params)
+    }
+    fn headers(
+        &self,
+    ) -> Result<Option<::api_builder::HeaderMap>, ::api_builder::error::HeaderError> {
+        let mut headers = ::api_builder::HeaderMap::new();
+        headers.append("x-request-id", self.x_request_id.to_string().parse()?);
+        Ok(Some(headers))
+    }
+}
+/// Create a pet.
+///
+/// `POST /pets`
+#[derive(Debug, Clone)]
+pub struct CreatePet {
+    /// The request body.
+    pub body: NewPet,
+}
+#[::api_builder::api_endpoint(
+    method = POST,
+    path = "\"pets\"",
+    response = CreatePetResponse
+)]
+impl ::api_builder::Endpoint for CreatePet {
+    fn body(
+        &self,
+    ) -> Result<
+        Option<(::std::borrow::Cow<'static, str>, Vec<u8>)>,
+        ::api_builder::error::BodyError,
+    > {
+        let body = &self.body;
+        Ok(Some(("application/json".into(), ::api_builder::serde_json::to_vec(body)?)))
+    }
+}
+/// `GET /pets/{petId}`
+#[derive(Debug, Clone)]
+pub struct GetPet {
+    pub pet_id: i64,
+}
+#[::api_builder::api_endpoint(
+    method = GET,
+    path = "format!(\"pets/{}\", self.pet_id)",
+    response = GetPetResponse
+)]
+impl ::api_builder::Endpoint for GetPet {
+    fn target(&self) -> ::api_builder::RequestTarget<'_> {
+        ::api_builder::RequestTarget::new()
+            .segment("pets")
+            .segment(::std::string::ToString::to_string(&self.pet_id))
+            .query(::api_builder::Endpoint::query_params(self))
+    }
+}
+/// `PUT /pets/{petId}`
+#[deprecated]
+#[derive(Debug, Clone)]
+pub struct UploadPhoto {
+    pub pet_id: i64,
+    /// The request body.
+    pub body: Option<Vec<u8>>,
+}
+#[allow(deprecated)]
+#[::api_builder::api_endpoint(method = PUT, path = "format!(\"pets/{}\", self.pet_id)")]
+impl ::api_builder::Endpoint for UploadPhoto {
+    fn target(&self) -> ::api_builder::RequestTarget<'_> {
+        ::api_builder::RequestTarget::new()
+            .segment("pets")
+            .segment(::std::string::ToString::to_string(&self.pet_id))
+            .query(::api_builder::Endpoint::query_params(self))
+    }
+    fn body(
+        &self,
+    ) -> Result<
+        Option<(::std::borrow::Cow<'static, str>, Vec<u8>)>,
+        ::api_builder::error::BodyError,
+    > {
+        match &self.body {
+            Some(body) => Ok(Some(("image/png".into(), body.clone()))),
+            None => Ok(None),
+        }
+    }
+}
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    ::serde::Serialize,
+    ::serde::Deserialize
+)]
+pub enum Status {
+    #[serde(rename = "available")]
+    Available,
+    #[serde(rename = "pending")]
+    Pending,
+    #[serde(rename = "sold")]
+    Sold,
+}
+impl Status {
+    /// The value of the variant.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Available => "available",
+            Self::Pending => "pending",
+            Self::Sold => "sold",
+        }
+    }
+}
+impl ::std::fmt::Display for Status {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+impl ::api_builder::ToQueryValue for Status {
+    fn to_query_value(&self) -> Option<::std::borrow::Cow<'static, str>> {
+        Some(::std::borrow::Cow::Borrowed(self.as_str()))
+    }
+}
+#[derive(Debug, Clone, PartialEq, ::serde::Serialize, ::serde::Deserialize)]
+pub struct Pet {
+    pub id: i64,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<Status>,
+}
+#[derive(Debug, Clone, PartialEq, ::serde::Serialize, ::serde::Deserialize)]
+pub struct NewPet {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+}
+#[derive(Debug, Clone, PartialEq, ::serde::Serialize, ::serde::Deserialize)]
+pub struct Filter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<Status>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+}
+/// The response of [`ListPets`].
+pub type ListPetsResponse = Vec<Pet>;
+/// The response of [`CreatePet`].
+pub type CreatePetResponse = Pet;
+/// The response of [`GetPet`].
+pub type GetPetResponse = Pet;
@@ -0,0 +1,121 @@
+//! Compares the allocations and time taken to build the URL of an endpoint, and to join it to a base URL.
+//!
+//! Run with `cargo bench --bench url`.
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    borrow::Cow,
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use api_builder::{Endpoint, QueryParamPair, QueryParamPairs, Url};
+
+/// Counts every allocation.
+struct Counting;
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// A search with a few string parameters.
+struct Search {
+    query: String,
+    sort: String,
+    cursor: Option<String>,
+    owned: bool,
+}
+impl Endpoint for Search {
+    fn path(&self) -> Cow<'static, str> {
+        Cow::Borrowed("search")
+    }
+
+    fn query_params(&self) -> Option<QueryParamPairs<'_>> {
+        let mut params = QueryParamPairs(Vec::with_capacity(3));
+        if self.owned {
+            // How the parameters had to be built when they were `'static`
+            params.0.push(QueryParamPair::new("q", self.query.clone()));
+            params
+                .0
+                .push(QueryParamPair::new("sort", self.sort.clone()));
+            if let Some(cursor) = &self.cursor {
+                params.0.push(QueryParamPair::new("cursor", cursor.clone()));
+            }
+        } else {
+            params.push(("q", &self.query));
+            params.push(("sort", &self.sort));
+            params.push(("cursor", &self.cursor));
+        }
+        Some(params)
+    }
+}
+
+/// Run `f` many times, printing the allocations and time of each run.
+fn bench(name: &str, mut f: impl FnMut()) {
+    const RUNS: usize = 200_000;
+    for _ in 0..1_000 {
+        f();
+    }
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        f();
+    }
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    println!(
+        "{name:<32} {:>6.2} allocations {:>8.1?}",
+        allocations as f64 / RUNS as f64,
+        elapsed / RUNS as u32,
+    );
+}
+
+fn main() {
+    let search = |owned| Search {
+        query: "rust http client".to_owned(),
+        sort: "relevance".to_owned(),
+        cursor: Some("c2Vjb25kIHBhZ2U=".to_owned()),
+        owned,
+    };
+
+    let owned = search(true);
+    bench("owned parameters, write", || {
+        let mut url = String::new();
+        black_box(&owned).target().write(&mut url).unwrap();
+        black_box(url);
+    });
+
+    let borrowed = search(false);
+    bench("borrowed parameters, write", || {
+        let mut url = String::new();
+        black_box(&borrowed).target().write(&mut url).unwrap();
+        black_box(url);
+    });
+
+    let mut buffer = String::new();
+    bench("borrowed parameters, reused buffer", || {
+        buffer.clear();
+        black_box(&borrowed).target().write(&mut buffer).unwrap();
+        black_box(&buffer);
+    });
+
+    let base = Url::parse("https://example.com/v1/").unwrap();
+    bench("borrowed parameters, joined", || {
+        black_box(black_box(&borrowed).target().join(&base).unwrap());
+    });
+}
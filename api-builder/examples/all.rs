@@ -1,4 +1,7 @@
-use api_builder::{Endpoint, Query as _, ReqwestClient, RestClient, api_endpoint, api_rest_client};
+use api_builder::{
+    Endpoint, Query as _, ReqwestClient, RestClient, TypedEndpoint as _, api_endpoint,
+    api_rest_client,
+};
 
 /// Any client errors.
 #[derive(Debug, thiserror::Error)]
@@ -26,8 +29,13 @@ struct Payload {
     test: String,
 }
 
-// Automatically implements `Endpoint` for `Payload`.
-#[api_endpoint(method = GET, path = "\"ab\"", self_as_body = "application/json")]
+// Automatically implements `Endpoint` and `TypedEndpoint` for `Payload`.
+#[api_endpoint(
+    method = GET,
+    path = "\"ab\"",
+    self_as_body = "application/json",
+    response = Response
+)]
 impl Endpoint for Payload {}
 
 // Add additional methods to the resource.
@@ -60,4 +68,5 @@ fn main() {
 
     let _response = payload.final_query(&client).unwrap();
     let _response: Response = payload.query(&client).unwrap();
+    let _response = payload.execute(&client).unwrap();
 }
@@ -1,4 +1,4 @@
-use api_builder::{ReqwestAsyncClient, RestClient, api_rest_client};
+use api_builder::{BaseUrl, ReqwestAsyncClient, RestClient, api_rest_client};
 
 /// Any client errors.
 #[derive(Debug, thiserror::Error)]
@@ -10,7 +10,49 @@ pub struct Client {
     /// Inner reqwest client.
     async_client: reqwest::Client,
 }
-#[api_rest_client(error = APIError, base = "\"https://example.com/v1/\"")]
+#[api_rest_client(error = APIError, base = "\"https://example.com/v1\"")]
 impl RestClient for Client {}
 
-fn main() {}
+/// A client which can be pointed at another environment, with `EXAMPLE_ENV=sandbox`.
+///
+/// `EXAMPLE_URL` overrides the environments entirely.
+#[derive(ReqwestAsyncClient)]
+pub struct EnvironmentClient {
+    /// Inner reqwest client.
+    async_client: reqwest::Client,
+}
+#[api_rest_client(
+    error = APIError,
+    base_env = "EXAMPLE_URL",
+    environments(
+        production = "https://example.com/v1",
+        sandbox = "https://sandbox.example.com/v1",
+        local = "http://localhost:8080"
+    ),
+    environment_env = "EXAMPLE_ENV"
+)]
+impl RestClient for EnvironmentClient {}
+
+/// A client whose base URL is chosen when it is created.
+#[derive(ReqwestAsyncClient)]
+pub struct FieldClient {
+    /// Inner reqwest client.
+    async_client: reqwest::Client,
+    /// The base URL of every request.
+    base: BaseUrl,
+}
+#[api_rest_client(error = APIError, base_field = base)]
+impl RestClient for FieldClient {}
+
+fn main() {
+    // Both are validated before any request is sent
+    let _client = EnvironmentClient {
+        async_client: reqwest::Client::new(),
+    }
+    .checked()
+    .unwrap();
+    let _client = FieldClient {
+        async_client: reqwest::Client::new(),
+        base: BaseUrl::parse("https://example.com/v1").unwrap(),
+    };
+}
@@ -1,7 +1,7 @@
 use api_builder::{Endpoint, api_endpoint};
 
 #[derive(serde::Deserialize)]
-struct Response {
+struct _Response {
     _success: bool,
 }
 
@@ -10,7 +10,12 @@ struct _Payload {
     id: String,
     test: String,
 }
-#[api_endpoint(method = GET, path = "\"ab\"", self_as_body = "application/json")]
+#[api_endpoint(
+    method = GET,
+    path = "\"ab\"",
+    self_as_body = "application/json",
+    timeout = "5s"
+)]
 impl Endpoint for _Payload {}
 
 fn main() {}
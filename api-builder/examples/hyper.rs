@@ -0,0 +1,24 @@
+use api_builder::{
+    HyperAsyncClient, HyperClient, RestClient, api_rest_client, hyper_rustls_client,
+};
+use hyper_rustls::HttpsConnector;
+use hyper_util::client::legacy::connect::HttpConnector;
+
+/// Any client errors.
+#[derive(Debug, thiserror::Error)]
+pub enum APIError {}
+
+/// The main client.
+#[derive(HyperAsyncClient)]
+pub struct Client {
+    /// Inner hyper client.
+    hyper_client: HyperClient<HttpsConnector<HttpConnector>>,
+}
+#[api_rest_client(error = APIError, base = "\"https://example.com/v1/\"")]
+impl RestClient for Client {}
+
+fn main() {
+    let _client = Client {
+        hyper_client: hyper_rustls_client(),
+    };
+}
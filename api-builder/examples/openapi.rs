@@ -0,0 +1,48 @@
+use api_builder::{DescribeEndpoint, Endpoint, QueryParamPairs, api_endpoint, openapi::OpenApi};
+
+/// A pet.
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+struct Pet {
+    _id: u64,
+    _name: String,
+}
+
+/// Get a pet.
+///
+/// Returns a single pet by its ID.
+#[derive(DescribeEndpoint)]
+#[describe(response = Pet)]
+struct GetPet {
+    /// The ID of the pet.
+    #[describe(path)]
+    id: u64,
+    /// Include the owner.
+    #[describe(query, rename = "withOwner")]
+    with_owner: Option<bool>,
+}
+#[api_endpoint(method = GET, path = "format!(\"pets/{}\", self.id)")]
+impl Endpoint for GetPet {
+    fn query_params(&self) -> Option<QueryParamPairs<'_>> {
+        let mut params = QueryParamPairs::default();
+        params.push(("withOwner", self.with_owner));
+        Some(params)
+    }
+}
+
+/// Create a pet.
+#[derive(serde::Serialize, schemars::JsonSchema, DescribeEndpoint)]
+#[describe(self_as_body = "application/json", response = Pet, status = 201)]
+struct CreatePet {
+    name: String,
+}
+#[api_endpoint(method = POST, path = "\"pets\"", self_as_body = "application/json")]
+impl Endpoint for CreatePet {}
+
+fn main() {
+    let document = OpenApi::new("Pet Store", "1.0.0")
+        .server("https://example.com/v1")
+        .endpoint::<GetPet>()
+        .endpoint::<CreatePet>()
+        .build();
+    println!("{}", serde_json::to_string_pretty(&document).unwrap());
+}
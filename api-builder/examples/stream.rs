@@ -0,0 +1,33 @@
+use api_builder::{
+    Endpoint, ReqwestClient, RestClient, Stream, StreamQuery as _, api_endpoint, api_rest_client,
+    download_to_file,
+};
+
+/// Any client errors.
+#[derive(Debug, thiserror::Error)]
+pub enum APIError {}
+
+/// The main client.
+#[derive(Default, ReqwestClient)]
+pub struct Client {
+    /// Inner reqwest client.
+    client: reqwest::blocking::Client,
+}
+#[api_rest_client(error = APIError, base = "\"https://example.com/v1/\"")]
+impl RestClient for Client {}
+
+/// A large export.
+struct Export;
+#[api_endpoint(method = GET, path = "\"export\"")]
+impl Endpoint for Export {}
+
+fn main() {
+    let client = Client::default();
+
+    // The body is never fully buffered in memory.
+    let response = Stream(Export).query(&client).unwrap();
+    download_to_file::<_, APIError>(response, "export.bin", |progress| {
+        println!("{} / {:?} bytes", progress.downloaded, progress.total);
+    })
+    .unwrap();
+}
@@ -0,0 +1,38 @@
+use api_builder::{Endpoint, Query as _, RestClient, UreqClient, api_endpoint, api_rest_client};
+
+/// Any client errors.
+#[derive(Debug, thiserror::Error)]
+pub enum APIError {}
+
+/// The main client.
+#[derive(UreqClient)]
+pub struct Client {
+    /// Inner ureq agent.
+    ureq_agent: ureq::Agent,
+}
+#[api_rest_client(error = APIError, base = "\"https://example.com/v1/\"")]
+impl RestClient for Client {}
+
+/// The expected response for the resource below.
+#[derive(serde::Deserialize)]
+struct Response {
+    _success: bool,
+}
+
+/// The main resource.
+struct Resource;
+#[api_endpoint(method = GET, path = "\"ab\"", timeout = "10s")]
+impl Endpoint for Resource {}
+
+fn main() {
+    let client = Client {
+        ureq_agent: ureq::Agent::new_with_defaults(),
+    };
+
+    // example.com doesn't serve this API, so expect an error describing the failed request
+    let response: Result<Response, _> = Resource.query(&client);
+    match response {
+        Ok(_) => println!("success"),
+        Err(e) => eprintln!("request failed: {e}"),
+    }
+}
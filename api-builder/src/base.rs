@@ -0,0 +1,75 @@
+use core::{fmt, str::FromStr};
+
+use http::Uri;
+use url::Url;
+
+use crate::{BaseUrlError, RequestTarget, TargetError};
+
+/// The base URL of a client, which every [`RequestTarget`] is appended to.
+///
+/// It is parsed and validated once, when created.
+/// The path of the base is always kept, whether or not it ends with `/`, so `https://example.com/v1` and `https://example.com/v1/` are the same.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct BaseUrl(Url);
+impl BaseUrl {
+    /// Parse a base URL, such as `https://example.com/v1`.
+    pub fn parse(input: &str) -> Result<Self, BaseUrlError> {
+        Self::try_from(Url::parse(input)?)
+    }
+
+    /// Parse the base URL within the environment variable `variable`.
+    pub fn from_env(variable: &str) -> Result<Self, BaseUrlError> {
+        let input = std::env::var(variable)
+            .map_err(|_| BaseUrlError::MissingVariable(variable.to_owned()))?;
+        Self::parse(&input)
+    }
+
+    /// Parse the base URL of the environment called `name`, from `(name, url)` pairs.
+    ///
+    /// For example, `BaseUrl::environment("sandbox", &[("production", "https://example.com"), ("sandbox", "https://sandbox.example.com")])`.
+    pub fn environment(name: &str, environments: &[(&str, &str)]) -> Result<Self, BaseUrlError> {
+        let (_, input) = environments
+            .iter()
+            .find(|(x, _)| x.eq_ignore_ascii_case(name))
+            .ok_or_else(|| BaseUrlError::UnknownEnvironment(name.to_owned()))?;
+        Self::parse(input)
+    }
+
+    /// The URL.
+    pub fn url(&self) -> &Url {
+        &self.0
+    }
+
+    /// Append `target` to the base URL.
+    pub fn join(&self, target: &RequestTarget<'_>) -> Result<Uri, TargetError> {
+        target.join(&self.0)
+    }
+}
+impl TryFrom<Url> for BaseUrl {
+    type Error = BaseUrlError;
+
+    fn try_from(mut url: Url) -> Result<Self, Self::Error> {
+        if url.cannot_be_a_base() {
+            return Err(BaseUrlError::CannotBeABase(url.into()));
+        }
+        url.set_fragment(None);
+        Ok(Self(url))
+    }
+}
+impl FromStr for BaseUrl {
+    type Err = BaseUrlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+impl AsRef<Url> for BaseUrl {
+    fn as_ref(&self) -> &Url {
+        &self.0
+    }
+}
+impl fmt::Display for BaseUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
@@ -0,0 +1,113 @@
+use bytes::Bytes;
+use http::{Request, Response, Uri};
+use tokio::runtime::{Builder, Handle, Runtime, RuntimeFlavor};
+
+use crate::{APIError, APIErrorKind, AsyncClient, Client, RequestBody, RequestTarget, RestClient};
+
+/// Where [`BlockOn`] runs its futures.
+#[derive(Debug)]
+enum Executor {
+    /// A runtime owned by the client.
+    Runtime(Runtime),
+    /// A handle to a runtime owned by the user.
+    Handle(Handle),
+}
+impl Executor {
+    /// Run `future` to completion, blocking the current thread.
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        match self {
+            Executor::Runtime(runtime) => runtime.block_on(future),
+            Executor::Handle(handle) => handle.block_on(future),
+        }
+    }
+}
+
+/// A client which drives any [`AsyncClient`] as a blocking [`Client`].
+///
+/// Each request blocks the current thread until the async client completes.
+/// Calling this from within a tokio runtime fails with [`APIErrorKind::AsyncContext`], instead of panicking.
+#[derive(Debug)]
+pub struct BlockOn<C> {
+    client: C,
+    executor: Executor,
+}
+impl<C> BlockOn<C> {
+    /// Wrap `client`, with a dedicated current-thread runtime.
+    pub fn new(client: C) -> std::io::Result<Self> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        Ok(Self {
+            client,
+            executor: Executor::Runtime(runtime),
+        })
+    }
+
+    /// Wrap `client`, running requests on the runtime behind `handle`.
+    ///
+    /// The runtime must be able to drive `client`, e.g. have IO and time enabled for `reqwest`.
+    /// Current-thread runtimes are rejected, since their IO and timers are only driven by [`Runtime::block_on`], so requests would never complete.
+    pub fn with_handle(client: C, handle: Handle) -> std::io::Result<Self> {
+        if handle.runtime_flavor() == RuntimeFlavor::CurrentThread {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "a current-thread runtime can't drive requests from a handle, use a multi-thread runtime or `BlockOn::new`",
+            ));
+        }
+        Ok(Self {
+            client,
+            executor: Executor::Handle(handle),
+        })
+    }
+
+    /// Get the inner client.
+    pub fn inner(&self) -> &C {
+        &self.client
+    }
+
+    /// Run `future`, unless we are already inside a runtime.
+    fn block_on<T, E>(
+        &self,
+        future: impl Future<Output = Result<T, APIError<E>>>,
+    ) -> Result<T, APIError<E>> {
+        if Handle::try_current().is_ok() {
+            return Err(APIErrorKind::AsyncContext.into());
+        }
+        self.executor.block_on(future)
+    }
+}
+impl<C> RestClient for BlockOn<C>
+where
+    C: RestClient,
+{
+    type Error = C::Error;
+
+    fn rest_endpoint(&self, target: &RequestTarget<'_>) -> Result<Uri, APIError<Self::Error>> {
+        self.client.rest_endpoint(target)
+    }
+}
+impl<C> Client for BlockOn<C>
+where
+    C: AsyncClient + Sync,
+{
+    fn rest(&self, request: Request<Vec<u8>>) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        self.block_on(self.client.rest_async(request))
+    }
+
+    fn rest_body(
+        &self,
+        request: Request<RequestBody>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        self.block_on(self.client.rest_body_async(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_current_thread_handles() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let error = BlockOn::with_handle((), runtime.handle().clone()).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
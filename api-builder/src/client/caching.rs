@@ -0,0 +1,819 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, SystemTime},
+};
+
+use bytes::Bytes;
+use http::{
+    HeaderMap, HeaderName, HeaderValue, Method, Request, Response, StatusCode, Uri,
+    header::{
+        AGE, AUTHORIZATION, CACHE_CONTROL, ETAG, IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+        IF_RANGE, IF_UNMODIFIED_SINCE, LAST_MODIFIED, VARY,
+    },
+};
+
+use crate::{APIError, AsyncClient, Client, RequestBody, RequestTarget, RestClient};
+
+/// A cached response, along with what is needed to check it is still usable.
+#[derive(Clone, Debug)]
+pub struct CacheEntry {
+    /// The cached response.
+    pub response: Response<Bytes>,
+    /// When the response was stored, or last revalidated.
+    pub stored_at: SystemTime,
+    /// The request headers named by `Vary`, and their values when the response was stored.
+    pub vary: Vec<(HeaderName, Option<HeaderValue>)>,
+}
+impl CacheEntry {
+    /// Whether the entry was stored for a request with the same `Vary` header values.
+    pub fn matches(&self, headers: &HeaderMap) -> bool {
+        self.vary
+            .iter()
+            .all(|(name, value)| headers.get(name) == value.as_ref())
+    }
+
+    /// The key of the variant of `url` with the `Vary` header values of `headers`.
+    fn variant_key(&self, url: &str, headers: &HeaderMap) -> String {
+        let mut key = url.to_string();
+        for (name, _) in &self.vary {
+            key.push('\n');
+            key.push_str(name.as_str());
+            key.push(':');
+            if let Some(value) = headers.get(name) {
+                key.push_str(&String::from_utf8_lossy(value.as_bytes()));
+            }
+        }
+        key
+    }
+}
+
+/// A storage backend for [`CachingClient`].
+///
+/// Entries are keyed by the request URL, which holds the latest response.
+/// Responses with `Vary` are also stored under the URL followed by the values of the request headers it names, one per line.
+pub trait CacheStorage {
+    /// Get an entry.
+    fn get(&self, key: &str) -> Option<CacheEntry>;
+
+    /// Insert or replace an entry.
+    fn put(&self, key: &str, entry: CacheEntry);
+
+    /// Remove an entry.
+    fn remove(&self, key: &str);
+}
+
+/// An in-memory cache which evicts the least recently used entry when full.
+#[derive(Debug)]
+pub struct MemoryCache {
+    capacity: usize,
+    inner: Mutex<MemoryCacheInner>,
+}
+#[derive(Debug, Default)]
+struct MemoryCacheInner {
+    tick: u64,
+    entries: HashMap<String, (u64, CacheEntry)>,
+    order: BTreeMap<u64, String>,
+}
+impl MemoryCache {
+    /// Create a cache holding at most `capacity` responses.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::default(),
+        }
+    }
+
+    /// The number of cached responses.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove every entry.
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.entries.clear();
+        inner.order.clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MemoryCacheInner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+impl Default for MemoryCache {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+impl CacheStorage for MemoryCache {
+    fn get(&self, key: &str) -> Option<CacheEntry> {
+        let mut inner = self.lock();
+        inner.tick += 1;
+        let tick = inner.tick;
+
+        // Mark as most recently used
+        let (used, entry) = inner.entries.get_mut(key)?;
+        let previous = core::mem::replace(used, tick);
+        let entry = entry.clone();
+        inner.order.remove(&previous);
+        inner.order.insert(tick, key.to_string());
+        Some(entry)
+    }
+
+    fn put(&self, key: &str, entry: CacheEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut inner = self.lock();
+        inner.tick += 1;
+        let tick = inner.tick;
+        if let Some((previous, _)) = inner.entries.insert(key.to_string(), (tick, entry)) {
+            inner.order.remove(&previous);
+        }
+        inner.order.insert(tick, key.to_string());
+
+        // Evict the least recently used
+        while inner.entries.len() > self.capacity {
+            let Some((_, key)) = inner.order.pop_first() else {
+                break;
+            };
+            inner.entries.remove(&key);
+        }
+    }
+
+    fn remove(&self, key: &str) {
+        let mut inner = self.lock();
+        if let Some((used, _)) = inner.entries.remove(key) {
+            inner.order.remove(&used);
+        }
+    }
+}
+
+/// A cache which stores each response as a file within a directory.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Debug)]
+pub struct DiskCache {
+    directory: std::path::PathBuf,
+}
+#[cfg(not(target_arch = "wasm32"))]
+impl DiskCache {
+    /// Create a cache within `directory`, creating it if it does not exist.
+    pub fn new(directory: impl Into<std::path::PathBuf>) -> std::io::Result<Self> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory)?;
+        Ok(Self { directory })
+    }
+
+    fn path(&self, key: &str) -> std::path::PathBuf {
+        // FNV-1a, so file names are stable across builds
+        let hash = key.bytes().fold(0xcbf29ce484222325u64, |hash, b| {
+            (hash ^ b as u64).wrapping_mul(0x100000001b3)
+        });
+        self.directory.join(format!("{hash:016x}"))
+    }
+
+    fn read(&self, key: &str) -> Option<CacheEntry> {
+        let file = std::fs::read(self.path(key)).ok()?;
+        let end = file.iter().position(|&b| b == b'\n')?;
+        let meta: DiskEntry = serde_json::from_slice(&file[..end]).ok()?;
+        if meta.key != key {
+            return None;
+        }
+
+        let mut response = Response::builder().status(meta.status);
+        for (name, value) in meta.headers {
+            response = response.header(name, value);
+        }
+        let vary = meta
+            .vary
+            .into_iter()
+            .map(|(name, value)| {
+                Some((
+                    HeaderName::try_from(name).ok()?,
+                    value.map(HeaderValue::try_from).transpose().ok()?,
+                ))
+            })
+            .collect::<Option<_>>()?;
+
+        Some(CacheEntry {
+            response: response
+                .body(Bytes::copy_from_slice(&file[end + 1..]))
+                .ok()?,
+            stored_at: SystemTime::UNIX_EPOCH + Duration::from_millis(meta.stored_at),
+            vary,
+        })
+    }
+
+    fn write(&self, key: &str, entry: &CacheEntry) -> std::io::Result<()> {
+        let meta = DiskEntry {
+            key: key.to_string(),
+            status: entry.response.status().as_u16(),
+            headers: entry
+                .response
+                .headers()
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.as_bytes().to_vec()))
+                .collect(),
+            vary: entry
+                .vary
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.as_ref().map(|x| x.as_bytes().to_vec())))
+                .collect(),
+            stored_at: entry
+                .stored_at
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+        };
+
+        let mut file = serde_json::to_vec(&meta)?;
+        file.push(b'\n');
+        file.extend_from_slice(entry.response.body());
+
+        // Write then rename, so readers never see a partial file.
+        // Each write gets its own temporary file, so concurrent writers of a key don't clobber each other
+        static WRITES: AtomicU64 = AtomicU64::new(0);
+        let path = self.path(key);
+        let tmp = path.with_extension(format!(
+            "{}.{}.tmp",
+            std::process::id(),
+            WRITES.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::write(&tmp, file)?;
+        std::fs::rename(tmp, path)
+    }
+}
+#[cfg(not(target_arch = "wasm32"))]
+impl CacheStorage for DiskCache {
+    fn get(&self, key: &str) -> Option<CacheEntry> {
+        self.read(key)
+    }
+
+    fn put(&self, key: &str, entry: CacheEntry) {
+        // Caching is best-effort
+        let _ = self.write(key, &entry);
+    }
+
+    fn remove(&self, key: &str) {
+        let _ = std::fs::remove_file(self.path(key));
+    }
+}
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct DiskEntry {
+    key: String,
+    status: u16,
+    headers: Vec<(String, Vec<u8>)>,
+    vary: Vec<(String, Option<Vec<u8>>)>,
+    stored_at: u64,
+}
+
+/// The `Cache-Control` directives relevant to caching responses.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+struct CacheControl {
+    no_store: bool,
+    no_cache: bool,
+    private: bool,
+    public: bool,
+    must_revalidate: bool,
+    max_age: Option<u64>,
+    s_maxage: Option<u64>,
+}
+impl CacheControl {
+    fn parse(headers: &HeaderMap) -> Self {
+        let mut cache_control = Self::default();
+        for value in headers.get_all(CACHE_CONTROL) {
+            let Ok(value) = value.to_str() else {
+                continue;
+            };
+
+            for directive in value.split(',') {
+                let (name, argument) = match directive.split_once('=') {
+                    Some((name, argument)) => (name, Some(argument.trim().trim_matches('"'))),
+                    None => (directive, None),
+                };
+                match name.trim().to_ascii_lowercase().as_str() {
+                    "no-store" => cache_control.no_store = true,
+                    "no-cache" => cache_control.no_cache = true,
+                    "private" => cache_control.private = true,
+                    "public" => cache_control.public = true,
+                    "must-revalidate" => cache_control.must_revalidate = true,
+                    "max-age" => cache_control.max_age = argument.and_then(|x| x.parse().ok()),
+                    "s-maxage" => cache_control.s_maxage = argument.and_then(|x| x.parse().ok()),
+                    _ => {}
+                }
+            }
+        }
+        cache_control
+    }
+
+    /// Whether a shared cache may reuse the response to a request with `Authorization`, see RFC 9111 §3.5.
+    fn shares_authorized(&self) -> bool {
+        self.public || self.s_maxage.is_some() || self.must_revalidate
+    }
+}
+
+/// The result of looking up a request in the cache.
+enum Lookup {
+    /// The request can't be cached.
+    Bypass,
+    /// Nothing usable was cached.
+    Miss,
+    /// The cached response is fresh.
+    Fresh(Response<Bytes>),
+    /// The cached response must be revalidated, the validators have been added to the request.
+    Stale(CacheEntry),
+}
+
+/// A client which caches `GET` responses.
+///
+/// Responses are reused while fresh according to `Cache-Control`, then revalidated with `If-None-Match`/`If-Modified-Since`.
+/// A `304 Not Modified` is turned back into the cached response.
+/// Requests which are already conditional, such as with `If-None-Match`, bypass the cache so the caller sees the server's answer.
+/// When the cache is shared, responses to requests with `Authorization` are only stored with `public`, `s-maxage` or `must-revalidate`.
+/// Successful requests with other methods invalidate the cached response for the same URL.
+#[derive(Clone, Debug, Default)]
+pub struct CachingClient<C, S = MemoryCache> {
+    client: C,
+    storage: S,
+    shared: bool,
+}
+impl<C, S> CachingClient<C, S> {
+    /// Wrap `client`, storing responses in `storage`.
+    ///
+    /// The cache is treated as shared, so `Cache-Control: private` responses are not stored.
+    pub fn new(client: C, storage: S) -> Self {
+        Self {
+            client,
+            storage,
+            shared: true,
+        }
+    }
+
+    /// Whether the cache is shared between users.
+    ///
+    /// A private cache stores `Cache-Control: private` responses and ignores `s-maxage`.
+    pub fn shared(mut self, shared: bool) -> Self {
+        self.shared = shared;
+        self
+    }
+
+    /// Get the inner client.
+    pub fn inner(&self) -> &C {
+        &self.client
+    }
+
+    /// Get the storage.
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+}
+impl<C, S> CachingClient<C, S>
+where
+    S: CacheStorage,
+{
+    /// Check the cache before sending the request.
+    fn lookup<T>(&self, request: &mut Request<T>) -> Lookup {
+        const CONDITIONAL: [HeaderName; 5] = [
+            IF_NONE_MATCH,
+            IF_MATCH,
+            IF_MODIFIED_SINCE,
+            IF_UNMODIFIED_SINCE,
+            IF_RANGE,
+        ];
+        if request.method() != Method::GET
+            || CacheControl::parse(request.headers()).no_store
+            || CONDITIONAL
+                .iter()
+                .any(|name| request.headers().contains_key(name))
+        {
+            return Lookup::Bypass;
+        }
+
+        let Some(entry) = self.lookup_variant(&request.uri().to_string(), request.headers()) else {
+            return Lookup::Miss;
+        };
+        if self.shared
+            && request.headers().contains_key(AUTHORIZATION)
+            && !CacheControl::parse(entry.response.headers()).shares_authorized()
+        {
+            return Lookup::Miss;
+        }
+        if self.is_fresh(&entry) && !CacheControl::parse(request.headers()).no_cache {
+            return Lookup::Fresh(entry.response);
+        }
+
+        // Add the validators
+        let cached = entry.response.headers();
+        let headers = request.headers_mut();
+        if let Some(etag) = cached.get(ETAG) {
+            headers.insert(IF_NONE_MATCH, etag.clone());
+        }
+        if let Some(last_modified) = cached.get(LAST_MODIFIED) {
+            headers.insert(IF_MODIFIED_SINCE, last_modified.clone());
+        }
+        Lookup::Stale(entry)
+    }
+
+    /// Get the entry stored for the URL `key` and the `Vary` header values of `headers`.
+    fn lookup_variant(&self, key: &str, headers: &HeaderMap) -> Option<CacheEntry> {
+        let entry = self.storage.get(key)?;
+        if entry.matches(headers) {
+            return Some(entry);
+        }
+
+        // The latest response was for another variant
+        self.storage
+            .get(&entry.variant_key(key, headers))
+            .filter(|x| x.matches(headers))
+    }
+
+    /// Update the cache once the response has been received.
+    fn store(
+        &self,
+        key: String,
+        method: &Method,
+        headers: &HeaderMap,
+        lookup: Lookup,
+        response: Response<Bytes>,
+    ) -> Response<Bytes> {
+        if method != Method::GET {
+            if !method.is_safe() && response.status().is_success() {
+                self.storage.remove(&key);
+            }
+            return response;
+        }
+
+        let response = match lookup {
+            Lookup::Bypass => return response,
+            Lookup::Stale(mut entry) if response.status() == StatusCode::NOT_MODIFIED => {
+                // Refresh the cached headers
+                let cached = entry.response.headers_mut();
+                for name in response.headers().keys() {
+                    cached.remove(name);
+                }
+                for (name, value) in response.headers() {
+                    cached.append(name, value.clone());
+                }
+                entry.response
+            }
+            _ => response,
+        };
+
+        if let Some(entry) = self.entry(headers, &response) {
+            if !entry.vary.is_empty() {
+                self.storage
+                    .put(&entry.variant_key(&key, headers), entry.clone());
+            }
+            self.storage.put(&key, entry);
+        }
+        response
+    }
+
+    /// Create an entry for the response, if it may be stored.
+    fn entry(&self, request: &HeaderMap, response: &Response<Bytes>) -> Option<CacheEntry> {
+        if response.status() != StatusCode::OK {
+            return None;
+        }
+
+        let cache_control = CacheControl::parse(response.headers());
+        if cache_control.no_store || (self.shared && cache_control.private) {
+            return None;
+        }
+        if self.shared && request.contains_key(AUTHORIZATION) && !cache_control.shares_authorized()
+        {
+            return None;
+        }
+
+        // Without freshness or validators, the entry would never be used
+        let headers = response.headers();
+        if cache_control.max_age.is_none()
+            && cache_control.s_maxage.is_none()
+            && !headers.contains_key(ETAG)
+            && !headers.contains_key(LAST_MODIFIED)
+        {
+            return None;
+        }
+
+        let mut vary = Vec::new();
+        for value in headers.get_all(VARY) {
+            for name in value.to_str().ok()?.split(',') {
+                let name = name.trim();
+                if name == "*" {
+                    return None;
+                }
+                let name = HeaderName::try_from(name).ok()?;
+                let value = request.get(&name).cloned();
+                vary.push((name, value));
+            }
+        }
+
+        Some(CacheEntry {
+            response: response.clone(),
+            // Without the current time the entry is never fresh, but can still be revalidated
+            stored_at: now().unwrap_or(SystemTime::UNIX_EPOCH),
+            vary,
+        })
+    }
+
+    /// Whether the entry can be used without revalidation.
+    fn is_fresh(&self, entry: &CacheEntry) -> bool {
+        let cache_control = CacheControl::parse(entry.response.headers());
+        if cache_control.no_cache {
+            return false;
+        }
+
+        let lifetime = self
+            .shared
+            .then_some(cache_control.s_maxage)
+            .flatten()
+            .or(cache_control.max_age);
+        let (Some(lifetime), Some(now)) = (lifetime, now()) else {
+            return false;
+        };
+
+        let initial_age = entry
+            .response
+            .headers()
+            .get(AGE)
+            .and_then(|x| x.to_str().ok())
+            .and_then(|x| x.parse().ok())
+            .unwrap_or(0);
+        let age = initial_age
+            + now
+                .duration_since(entry.stored_at)
+                .unwrap_or_default()
+                .as_secs();
+        age < lifetime
+    }
+}
+/// The current time, if it is available.
+fn now() -> Option<SystemTime> {
+    // The current time isn't available everywhere on wasm
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        Some(SystemTime::now())
+    }
+    #[cfg(target_arch = "wasm32")]
+    None
+}
+
+impl<C, S> RestClient for CachingClient<C, S>
+where
+    C: RestClient,
+{
+    type Error = C::Error;
+
+    fn rest_endpoint(&self, target: &RequestTarget<'_>) -> Result<Uri, APIError<Self::Error>> {
+        self.client.rest_endpoint(target)
+    }
+}
+impl<C, S> Client for CachingClient<C, S>
+where
+    C: Client,
+    S: CacheStorage,
+{
+    fn rest(
+        &self,
+        mut request: Request<Vec<u8>>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let lookup = match self.lookup(&mut request) {
+            Lookup::Fresh(response) => return Ok(response),
+            lookup => lookup,
+        };
+
+        let key = request.uri().to_string();
+        let method = request.method().clone();
+        let headers = request.headers().clone();
+        let response = self.client.rest(request)?;
+        Ok(self.store(key, &method, &headers, lookup, response))
+    }
+
+    fn rest_body(
+        &self,
+        request: Request<RequestBody>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let key = request.uri().to_string();
+        let method = request.method().clone();
+        let headers = request.headers().clone();
+        let response = self.client.rest_body(request)?;
+        Ok(self.store(key, &method, &headers, Lookup::Bypass, response))
+    }
+}
+impl<C, S> AsyncClient for CachingClient<C, S>
+where
+    C: AsyncClient + Sync,
+    S: CacheStorage + Sync,
+{
+    async fn rest_async(
+        &self,
+        mut request: Request<Vec<u8>>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let lookup = match self.lookup(&mut request) {
+            Lookup::Fresh(response) => return Ok(response),
+            lookup => lookup,
+        };
+
+        let key = request.uri().to_string();
+        let method = request.method().clone();
+        let headers = request.headers().clone();
+        let response = self.client.rest_async(request).await?;
+        Ok(self.store(key, &method, &headers, lookup, response))
+    }
+
+    async fn rest_body_async(
+        &self,
+        request: Request<RequestBody>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let key = request.uri().to_string();
+        let method = request.method().clone();
+        let headers = request.headers().clone();
+        let response = self.client.rest_body_async(request).await?;
+        Ok(self.store(key, &method, &headers, Lookup::Bypass, response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use http::header::ACCEPT_LANGUAGE;
+
+    use super::*;
+
+    /// A server which answers in the requested language, or `304` when the `ETag` matches.
+    #[derive(Default)]
+    struct Upstream {
+        sent: AtomicU32,
+    }
+    impl RestClient for Upstream {
+        type Error = ();
+
+        fn rest_endpoint(&self, _: &RequestTarget<'_>) -> Result<Uri, APIError<()>> {
+            unreachable!()
+        }
+    }
+    impl Client for Upstream {
+        fn rest(&self, request: Request<Vec<u8>>) -> Result<Response<Bytes>, APIError<()>> {
+            self.sent.fetch_add(1, Ordering::Relaxed);
+            let language = request
+                .headers()
+                .get(ACCEPT_LANGUAGE)
+                .map_or("en", |x| x.to_str().unwrap())
+                .to_string();
+            let etag = format!("\"{language}\"");
+            let status = match request.headers().get(IF_NONE_MATCH) {
+                Some(x) if x == etag.as_str() => StatusCode::NOT_MODIFIED,
+                _ => StatusCode::OK,
+            };
+            Ok(Response::builder()
+                .status(status)
+                .header(CACHE_CONTROL, "max-age=60")
+                .header(ETAG, etag)
+                .header(VARY, "Accept-Language")
+                .body(Bytes::from(language))?)
+        }
+    }
+
+    fn get(client: &CachingClient<Upstream>, headers: &[(HeaderName, &str)]) -> Response<Bytes> {
+        let mut request = Request::get("https://example.com/greeting");
+        for (name, value) in headers {
+            request = request.header(name, *value);
+        }
+        client.rest(request.body(Vec::new()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn keeps_each_variant() {
+        let client = CachingClient::new(Upstream::default(), MemoryCache::default());
+        for _ in 0..2 {
+            for language in ["en", "fr", "de"] {
+                let response = get(&client, &[(ACCEPT_LANGUAGE, language)]);
+                assert_eq!(response.body(), language);
+            }
+        }
+        assert_eq!(client.inner().sent.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn bypasses_conditional_requests() {
+        let client = CachingClient::new(Upstream::default(), MemoryCache::default());
+        assert_eq!(get(&client, &[]).status(), StatusCode::OK);
+
+        // The caller's validator is answered by the server, not the cache
+        let response = get(&client, &[(IF_NONE_MATCH, "\"en\"")]);
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        let response = get(&client, &[(IF_NONE_MATCH, "\"fr\"")]);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(client.inner().sent.load(Ordering::Relaxed), 3);
+    }
+
+    /// A server which greets whoever is authorized, with the given `Cache-Control`.
+    struct Greeter {
+        cache_control: &'static str,
+        sent: AtomicU32,
+    }
+    impl RestClient for Greeter {
+        type Error = ();
+
+        fn rest_endpoint(&self, _: &RequestTarget<'_>) -> Result<Uri, APIError<()>> {
+            unreachable!()
+        }
+    }
+    impl Client for Greeter {
+        fn rest(&self, request: Request<Vec<u8>>) -> Result<Response<Bytes>, APIError<()>> {
+            self.sent.fetch_add(1, Ordering::Relaxed);
+            let user = request
+                .headers()
+                .get(AUTHORIZATION)
+                .map_or("anonymous", |x| x.to_str().unwrap())
+                .to_string();
+            Ok(Response::builder()
+                .header(CACHE_CONTROL, self.cache_control)
+                .body(Bytes::from(user))?)
+        }
+    }
+
+    fn greet(client: &CachingClient<Greeter>, user: Option<&str>) -> Bytes {
+        let mut request = Request::get("https://example.com/me");
+        if let Some(user) = user {
+            request = request.header(AUTHORIZATION, user);
+        }
+        client
+            .rest(request.body(Vec::new()).unwrap())
+            .unwrap()
+            .into_body()
+    }
+
+    #[test]
+    fn keeps_authorized_responses_private() {
+        let greeter = Greeter {
+            cache_control: "max-age=60",
+            sent: AtomicU32::new(0),
+        };
+        let client = CachingClient::new(greeter, MemoryCache::default());
+        assert_eq!(greet(&client, Some("alice")), "alice");
+        assert_eq!(greet(&client, None), "anonymous");
+        assert_eq!(greet(&client, Some("bob")), "bob");
+        assert_eq!(client.inner().sent.load(Ordering::Relaxed), 3);
+
+        // A private cache belongs to one user, so it may reuse the response
+        let greeter = Greeter {
+            cache_control: "max-age=60",
+            sent: AtomicU32::new(0),
+        };
+        let client = CachingClient::new(greeter, MemoryCache::default()).shared(false);
+        assert_eq!(greet(&client, Some("alice")), "alice");
+        assert_eq!(greet(&client, None), "alice");
+        assert_eq!(client.inner().sent.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn shares_public_authorized_responses() {
+        let greeter = Greeter {
+            cache_control: "public, max-age=60",
+            sent: AtomicU32::new(0),
+        };
+        let client = CachingClient::new(greeter, MemoryCache::default());
+        assert_eq!(greet(&client, Some("alice")), "alice");
+        assert_eq!(greet(&client, None), "alice");
+        assert_eq!(client.inner().sent.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn writes_to_disk_concurrently() {
+        let directory =
+            std::env::temp_dir().join(format!("api-builder-cache-{}", std::process::id()));
+        let cache = DiskCache::new(&directory).unwrap();
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let cache = &cache;
+                scope.spawn(move || {
+                    let entry = CacheEntry {
+                        response: Response::new(Bytes::from(format!("response {i}"))),
+                        stored_at: SystemTime::now(),
+                        vary: Vec::new(),
+                    };
+                    for _ in 0..16 {
+                        cache.put("key", entry.clone());
+                    }
+                });
+            }
+        });
+
+        let entry = cache.get("key").unwrap();
+        assert!(entry.response.body().starts_with(b"response "));
+        let files = std::fs::read_dir(&directory).unwrap().count();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(files, 1);
+    }
+}
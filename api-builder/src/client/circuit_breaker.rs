@@ -0,0 +1,509 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use http::{Request, Response, StatusCode, Uri};
+
+use crate::{APIError, APIErrorKind, AsyncClient, Client, RequestBody, RequestTarget, RestClient};
+
+/// A source of the current time, so tests can control it.
+pub trait Clock {
+    /// The current instant.
+    fn now(&self) -> Instant;
+}
+
+/// The system clock.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct SystemClock;
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+impl<K: Clock> Clock for std::sync::Arc<K> {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+}
+
+/// What failures are counted against.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub enum CircuitScope {
+    /// One circuit per host.
+    #[default]
+    Host,
+    /// One circuit per host and the first `n` path segments.
+    PathPrefix(usize),
+}
+
+/// The state of a circuit.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub enum CircuitState {
+    /// Requests are sent, failures are counted.
+    #[default]
+    Closed,
+    /// Requests fail fast until the cool-down has passed.
+    Open,
+    /// A limited number of probe requests are sent to check the upstream has recovered.
+    HalfOpen,
+}
+
+#[derive(Clone, Debug)]
+enum Circuit {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen { in_flight: u32, successes: u32 },
+}
+
+/// A client which stops sending requests to an upstream that keeps failing.
+///
+/// Transport errors, timeouts and responses with a failure status (`500`, `502`, `503` and `504` by default) are counted.
+/// After `failure_threshold` consecutive failures the circuit opens and requests fail fast with [`APIErrorKind::CircuitOpen`].
+/// Once the cool-down has passed, up to `probes` requests are let through: if they all succeed the circuit closes, otherwise it opens again.
+pub struct CircuitBreakerClient<C, K = SystemClock> {
+    client: C,
+    clock: K,
+    scope: CircuitScope,
+    failure_threshold: u32,
+    cool_down: Duration,
+    probes: u32,
+    failure_statuses: Vec<StatusCode>,
+    circuits: Mutex<HashMap<String, Circuit>>,
+}
+impl<C> CircuitBreakerClient<C> {
+    /// Wrap `client`.
+    ///
+    /// Defaults to opening after 5 failures per host, for 30 seconds, with 1 probe.
+    pub fn new(client: C) -> Self {
+        Self {
+            client,
+            clock: SystemClock,
+            scope: CircuitScope::Host,
+            failure_threshold: 5,
+            cool_down: Duration::from_secs(30),
+            probes: 1,
+            failure_statuses: vec![
+                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            circuits: Mutex::default(),
+        }
+    }
+}
+impl<C, K> CircuitBreakerClient<C, K> {
+    /// Use a different clock.
+    pub fn clock<K2: Clock>(self, clock: K2) -> CircuitBreakerClient<C, K2> {
+        CircuitBreakerClient {
+            client: self.client,
+            clock,
+            scope: self.scope,
+            failure_threshold: self.failure_threshold,
+            cool_down: self.cool_down,
+            probes: self.probes,
+            failure_statuses: self.failure_statuses,
+            circuits: self.circuits,
+        }
+    }
+
+    /// What failures are counted against.
+    pub fn scope(mut self, scope: CircuitScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// The number of consecutive failures before the circuit opens.
+    pub fn failure_threshold(mut self, failure_threshold: u32) -> Self {
+        self.failure_threshold = failure_threshold.max(1);
+        self
+    }
+
+    /// How long the circuit stays open before probing.
+    pub fn cool_down(mut self, cool_down: Duration) -> Self {
+        self.cool_down = cool_down;
+        self
+    }
+
+    /// The number of successful probes needed to close the circuit, which is also the number of probes allowed at once.
+    pub fn probes(mut self, probes: u32) -> Self {
+        self.probes = probes.max(1);
+        self
+    }
+
+    /// The response statuses counted as failures.
+    pub fn failure_statuses(mut self, statuses: impl IntoIterator<Item = StatusCode>) -> Self {
+        self.failure_statuses = statuses.into_iter().collect();
+        self
+    }
+
+    /// Get the inner client.
+    pub fn inner(&self) -> &C {
+        &self.client
+    }
+
+    fn circuits(&self) -> MutexGuard<'_, HashMap<String, Circuit>> {
+        self.circuits.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The circuit a request belongs to.
+    pub fn key(&self, uri: &Uri) -> String {
+        let mut key = uri
+            .authority()
+            .map(|x| x.as_str())
+            .unwrap_or_default()
+            .to_string();
+        if let CircuitScope::PathPrefix(segments) = self.scope {
+            for segment in uri
+                .path()
+                .split('/')
+                .filter(|x| !x.is_empty())
+                .take(segments)
+            {
+                key.push('/');
+                key.push_str(segment);
+            }
+        }
+        key
+    }
+}
+impl<C, K> CircuitBreakerClient<C, K>
+where
+    K: Clock,
+{
+    /// The current state of a circuit, see [`CircuitBreakerClient::key`].
+    pub fn state(&self, key: &str) -> CircuitState {
+        match self.circuits().get(key) {
+            None | Some(Circuit::Closed { .. }) => CircuitState::Closed,
+            Some(Circuit::Open { until }) if self.clock.now() >= *until => CircuitState::HalfOpen,
+            Some(Circuit::Open { .. }) => CircuitState::Open,
+            Some(Circuit::HalfOpen { .. }) => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Check the circuit allows a request.
+    fn acquire<E>(&self, key: &str) -> Result<Permit<'_, C, K>, APIError<E>> {
+        let mut circuits = self.circuits();
+        // Closed circuits without failures aren't kept
+        let probe = match circuits.get_mut(key) {
+            None => false,
+            Some(circuit) => {
+                if let Circuit::Open { until } = circuit {
+                    if self.clock.now() < *until {
+                        Err(APIErrorKind::CircuitOpen(key.to_string()))?
+                    }
+                    *circuit = Circuit::HalfOpen {
+                        in_flight: 0,
+                        successes: 0,
+                    };
+                }
+
+                match circuit {
+                    Circuit::HalfOpen { in_flight, .. } if *in_flight >= self.probes => {
+                        Err(APIErrorKind::CircuitOpen(key.to_string()))?
+                    }
+                    Circuit::HalfOpen { in_flight, .. } => {
+                        *in_flight += 1;
+                        true
+                    }
+                    _ => false,
+                }
+            }
+        };
+
+        Ok(Permit {
+            client: self,
+            key: key.to_string(),
+            probe,
+            done: false,
+        })
+    }
+
+    /// Whether the result counts as a failure.
+    fn is_failure<E>(&self, result: &Result<Response<Bytes>, APIError<E>>) -> bool {
+        match result {
+            Ok(response) => self.failure_statuses.contains(&response.status()),
+            Err(e) => is_send_failure(e.kind()),
+        }
+    }
+}
+
+/// Whether the request failed to be sent or answered, rather than being rejected before it was sent.
+fn is_send_failure<E>(kind: &APIErrorKind<E>) -> bool {
+    match kind {
+        APIErrorKind::HttpClient(_) | APIErrorKind::Client(_) | APIErrorKind::Timeout(_) => true,
+        APIErrorKind::Shared(e) => is_send_failure(e.kind()),
+        kind => kind.is_timeout() || kind.is_connect(),
+    }
+}
+
+/// Allows a single request through the circuit.
+struct Permit<'a, C, K>
+where
+    K: Clock,
+{
+    client: &'a CircuitBreakerClient<C, K>,
+    key: String,
+    probe: bool,
+    done: bool,
+}
+impl<C, K> Permit<'_, C, K>
+where
+    K: Clock,
+{
+    /// Record the outcome of the request.
+    fn record(mut self, failure: bool) {
+        self.done = true;
+
+        let client = self.client;
+        let mut circuits = client.circuits();
+        let circuit = circuits
+            .entry(self.key.clone())
+            .or_insert(Circuit::Closed { failures: 0 });
+
+        *circuit = match (circuit.clone(), failure) {
+            (Circuit::Closed { .. }, false) => Circuit::Closed { failures: 0 },
+            (Circuit::Closed { failures }, true) if failures + 1 < client.failure_threshold => {
+                Circuit::Closed {
+                    failures: failures + 1,
+                }
+            }
+            (Circuit::HalfOpen { successes, .. }, false)
+                if self.probe && successes + 1 >= client.probes =>
+            {
+                Circuit::Closed { failures: 0 }
+            }
+            (
+                Circuit::HalfOpen {
+                    in_flight,
+                    successes,
+                },
+                false,
+            ) if self.probe => Circuit::HalfOpen {
+                in_flight: in_flight.saturating_sub(1),
+                successes: successes + 1,
+            },
+            (Circuit::Open { until }, _) => Circuit::Open { until },
+            (circuit @ Circuit::HalfOpen { .. }, false) => circuit,
+            (_, true) => Circuit::Open {
+                until: client.clock.now() + client.cool_down,
+            },
+        };
+
+        // Otherwise a circuit would be kept for every key ever requested
+        if let Circuit::Closed { failures: 0 } = circuit {
+            circuits.remove(&self.key);
+        }
+    }
+}
+impl<C, K> Drop for Permit<'_, C, K>
+where
+    K: Clock,
+{
+    fn drop(&mut self) {
+        // Free the probe slot if the request was cancelled
+        if self.done || !self.probe {
+            return;
+        }
+
+        if let Some(Circuit::HalfOpen { in_flight, .. }) = self.client.circuits().get_mut(&self.key)
+        {
+            *in_flight = in_flight.saturating_sub(1);
+        }
+    }
+}
+
+impl<C, K> RestClient for CircuitBreakerClient<C, K>
+where
+    C: RestClient,
+{
+    type Error = C::Error;
+
+    fn rest_endpoint(&self, target: &RequestTarget<'_>) -> Result<Uri, APIError<Self::Error>> {
+        self.client.rest_endpoint(target)
+    }
+}
+impl<C, K> Client for CircuitBreakerClient<C, K>
+where
+    C: Client,
+    K: Clock,
+{
+    fn rest(&self, request: Request<Vec<u8>>) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let permit = self.acquire(&self.key(request.uri()))?;
+        let result = self.client.rest(request);
+        permit.record(self.is_failure(&result));
+        result
+    }
+
+    fn rest_body(
+        &self,
+        request: Request<RequestBody>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let permit = self.acquire(&self.key(request.uri()))?;
+        let result = self.client.rest_body(request);
+        permit.record(self.is_failure(&result));
+        result
+    }
+}
+impl<C, K> AsyncClient for CircuitBreakerClient<C, K>
+where
+    C: AsyncClient + Sync,
+    C::Error: Send,
+    K: Clock + Sync,
+{
+    async fn rest_async(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let permit = self.acquire(&self.key(request.uri()))?;
+        let result = self.client.rest_async(request).await;
+        permit.record(self.is_failure(&result));
+        result
+    }
+
+    async fn rest_body_async(
+        &self,
+        request: Request<RequestBody>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let permit = self.acquire(&self.key(request.uri()))?;
+        let result = self.client.rest_body_async(request).await;
+        permit.record(self.is_failure(&result));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    /// A clock which only moves when told to.
+    struct ManualClock(Mutex<Instant>);
+    impl ManualClock {
+        fn advance(&self, duration: Duration) {
+            *self.0.lock().unwrap() += duration;
+        }
+    }
+    impl Clock for ManualClock {
+        fn now(&self) -> Instant {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    /// How the upstream responds.
+    #[derive(Copy, Clone)]
+    enum Outcome {
+        Ok,
+        Unavailable,
+        Timeout,
+    }
+
+    /// A client which counts requests and responds with `outcome`.
+    struct Upstream {
+        outcome: Mutex<Outcome>,
+        sent: Mutex<u32>,
+    }
+    impl Upstream {
+        fn respond(&self, outcome: Outcome) {
+            *self.outcome.lock().unwrap() = outcome;
+        }
+    }
+    impl RestClient for Upstream {
+        type Error = ();
+
+        fn rest_endpoint(&self, _: &RequestTarget<'_>) -> Result<Uri, APIError<()>> {
+            unreachable!()
+        }
+    }
+    impl Client for Upstream {
+        fn rest(&self, _: Request<Vec<u8>>) -> Result<Response<Bytes>, APIError<()>> {
+            *self.sent.lock().unwrap() += 1;
+            let status = match *self.outcome.lock().unwrap() {
+                Outcome::Ok => StatusCode::OK,
+                Outcome::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+                Outcome::Timeout => Err(APIErrorKind::Timeout(Duration::from_secs(1)))?,
+            };
+            Ok(Response::builder().status(status).body(Bytes::new())?)
+        }
+    }
+
+    const KEY: &str = "example.com";
+
+    type Breaker = CircuitBreakerClient<Upstream, Arc<ManualClock>>;
+
+    fn breaker(outcome: Outcome) -> (Breaker, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock(Mutex::new(Instant::now())));
+        let upstream = Upstream {
+            outcome: Mutex::new(outcome),
+            sent: Mutex::new(0),
+        };
+        let client = CircuitBreakerClient::new(upstream)
+            .failure_threshold(3)
+            .cool_down(Duration::from_secs(10))
+            .clock(clock.clone());
+        (client, clock)
+    }
+
+    fn send(client: &Breaker) -> Result<StatusCode, APIError<()>> {
+        let request = Request::get("https://example.com/pets")
+            .body(Vec::new())
+            .unwrap();
+        client.rest(request).map(|x| x.status())
+    }
+
+    #[test]
+    fn opens_probes_and_closes() {
+        let (client, clock) = breaker(Outcome::Unavailable);
+        for _ in 0..3 {
+            assert_eq!(client.state(KEY), CircuitState::Closed);
+            assert_eq!(send(&client).unwrap(), StatusCode::SERVICE_UNAVAILABLE);
+        }
+
+        // Requests fail fast without being sent
+        assert_eq!(client.state(KEY), CircuitState::Open);
+        let error = send(&client).unwrap_err();
+        assert!(matches!(error.kind(), APIErrorKind::CircuitOpen(x) if x == KEY));
+        assert_eq!(*client.inner().sent.lock().unwrap(), 3);
+
+        // A failed probe opens the circuit again
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(client.state(KEY), CircuitState::HalfOpen);
+        send(&client).unwrap();
+        assert_eq!(client.state(KEY), CircuitState::Open);
+
+        // A successful probe closes it
+        clock.advance(Duration::from_secs(10));
+        client.inner().respond(Outcome::Ok);
+        assert_eq!(send(&client).unwrap(), StatusCode::OK);
+        assert_eq!(client.state(KEY), CircuitState::Closed);
+        assert!(client.circuits().is_empty());
+    }
+
+    #[test]
+    fn counts_consecutive_failures() {
+        let (client, _) = breaker(Outcome::Ok);
+        for _ in 0..5 {
+            client.inner().respond(Outcome::Unavailable);
+            send(&client).unwrap();
+            send(&client).unwrap();
+            // A success resets the count
+            client.inner().respond(Outcome::Ok);
+            send(&client).unwrap();
+            assert_eq!(client.state(KEY), CircuitState::Closed);
+        }
+        assert!(client.circuits().is_empty());
+    }
+
+    #[test]
+    fn counts_timeouts() {
+        let (client, _) = breaker(Outcome::Timeout);
+        for _ in 0..3 {
+            assert!(send(&client).unwrap_err().is_timeout());
+        }
+        assert_eq!(client.state(KEY), CircuitState::Open);
+    }
+}
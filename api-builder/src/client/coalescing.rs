@@ -0,0 +1,363 @@
+use core::{
+    future::poll_fn,
+    task::{Poll, Waker},
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use bytes::Bytes;
+use http::{HeaderName, Method, Request, Response, Uri};
+
+use crate::{APIError, APIErrorKind, AsyncClient, RequestBody, RequestTarget, RestClient};
+
+/// Identifies identical requests.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct FlightKey {
+    method: Method,
+    uri: String,
+    headers: Vec<(String, Vec<u8>)>,
+}
+
+/// The result shared with every caller.
+type FlightResult<E> = Result<Response<Bytes>, Arc<APIError<E>>>;
+
+/// A request which is in flight.
+struct Flight<E> {
+    state: Mutex<FlightState<E>>,
+}
+struct FlightState<E> {
+    result: Option<FlightResult<E>>,
+    waiters: usize,
+    wakers: Vec<Waker>,
+}
+impl<E> Flight<E> {
+    fn lock(&self) -> MutexGuard<'_, FlightState<E>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Publish the result and wake every waiter.
+    fn complete(&self, result: FlightResult<E>) {
+        let mut state = self.lock();
+        state.result = Some(result);
+        for waker in state.wakers.drain(..) {
+            waker.wake();
+        }
+    }
+}
+
+/// A client which shares the response of identical in-flight requests.
+///
+/// While a `GET`, `HEAD` or `OPTIONS` request without a body is in flight, identical requests wait for it instead of being sent.
+/// Requests are identical when they have the same method, URL and headers, see [`CoalescingClient::key_headers`].
+/// Errors are returned to every waiter as [`APIErrorKind::Shared`].
+pub struct CoalescingClient<C>
+where
+    C: RestClient,
+{
+    client: C,
+    key_headers: Option<Vec<HeaderName>>,
+    flights: Mutex<HashMap<FlightKey, Arc<Flight<C::Error>>>>,
+}
+impl<C> CoalescingClient<C>
+where
+    C: RestClient,
+{
+    /// Wrap `client`.
+    pub fn new(client: C) -> Self {
+        Self {
+            client,
+            key_headers: None,
+            flights: Mutex::default(),
+        }
+    }
+
+    /// Only compare these headers when checking if requests are identical.
+    ///
+    /// By default, every header is compared.
+    pub fn key_headers(mut self, headers: impl IntoIterator<Item = HeaderName>) -> Self {
+        self.key_headers = Some(headers.into_iter().collect());
+        self
+    }
+
+    /// Get the inner client.
+    pub fn inner(&self) -> &C {
+        &self.client
+    }
+
+    fn flights(&self) -> MutexGuard<'_, HashMap<FlightKey, Arc<Flight<C::Error>>>> {
+        self.flights.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The key for the request, if it may be coalesced.
+    fn key(&self, request: &Request<Vec<u8>>) -> Option<FlightKey> {
+        let method = request.method();
+        if !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+            || !request.body().is_empty()
+        {
+            return None;
+        }
+
+        let mut headers: Vec<_> = match &self.key_headers {
+            Some(names) => names
+                .iter()
+                .flat_map(|name| {
+                    request
+                        .headers()
+                        .get_all(name)
+                        .iter()
+                        .map(|value| (name.to_string(), value.as_bytes().to_vec()))
+                })
+                .collect(),
+            None => request
+                .headers()
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.as_bytes().to_vec()))
+                .collect(),
+        };
+        headers.sort();
+
+        Some(FlightKey {
+            method: method.clone(),
+            uri: request.uri().to_string(),
+            headers,
+        })
+    }
+}
+impl<C> RestClient for CoalescingClient<C>
+where
+    C: RestClient,
+{
+    type Error = C::Error;
+
+    fn rest_endpoint(&self, target: &RequestTarget<'_>) -> Result<Uri, APIError<Self::Error>> {
+        self.client.rest_endpoint(target)
+    }
+}
+impl<C> AsyncClient for CoalescingClient<C>
+where
+    C: AsyncClient + Sync,
+    C::Error: Send + Sync,
+{
+    async fn rest_async(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let Some(key) = self.key(&request) else {
+            return self.client.rest_async(request).await;
+        };
+
+        // Join the request in flight, or lead a new one
+        let (flight, leader) = {
+            let mut flights = self.flights();
+            match flights.get(&key) {
+                Some(flight) => {
+                    flight.lock().waiters += 1;
+                    (flight.clone(), false)
+                }
+                None => {
+                    let flight = Arc::new(Flight {
+                        state: Mutex::new(FlightState {
+                            result: None,
+                            waiters: 0,
+                            wakers: Vec::new(),
+                        }),
+                    });
+                    flights.insert(key.clone(), flight.clone());
+                    (flight, true)
+                }
+            }
+        };
+
+        if !leader {
+            let result = poll_fn(|cx| {
+                let mut state = flight.lock();
+                match &state.result {
+                    Some(result) => Poll::Ready(result.clone()),
+                    None => {
+                        state.wakers.push(cx.waker().clone());
+                        Poll::Pending
+                    }
+                }
+            })
+            .await;
+            return result.map_err(|e| APIErrorKind::Shared(e).into());
+        }
+
+        // Release the waiters if this future is dropped before completing
+        let mut guard = LeaderGuard {
+            client: self,
+            key: &key,
+            flight: &flight,
+            done: false,
+        };
+        let result = self.client.rest_async(request).await;
+        guard.done = true;
+
+        // No new callers can join once removed
+        self.flights().remove(&key);
+        let waiters = flight.lock().waiters;
+        match result {
+            Ok(response) => {
+                flight.complete(Ok(response.clone()));
+                Ok(response)
+            }
+            Err(e) if waiters == 0 => Err(e),
+            Err(e) => {
+                let e = Arc::new(e);
+                flight.complete(Err(e.clone()));
+                Err(APIErrorKind::Shared(e).into())
+            }
+        }
+    }
+
+    async fn rest_body_async(
+        &self,
+        request: Request<RequestBody>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        self.client.rest_body_async(request).await
+    }
+}
+
+/// Completes the flight with an error if the leader is cancelled.
+struct LeaderGuard<'a, C>
+where
+    C: RestClient,
+{
+    client: &'a CoalescingClient<C>,
+    key: &'a FlightKey,
+    flight: &'a Arc<Flight<C::Error>>,
+    done: bool,
+}
+impl<C> Drop for LeaderGuard<'_, C>
+where
+    C: RestClient,
+{
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+
+        self.client.flights().remove(self.key);
+        self.flight.complete(Err(Arc::new(
+            APIErrorKind::Other(anyhow::anyhow!("the coalesced request was cancelled")).into(),
+        )));
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "stream", feature = "tokio"))]
+mod tests {
+    use core::time::Duration;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use futures_util::future::join_all;
+
+    use super::*;
+
+    /// A server which answers with the path, yielding once so identical requests can join.
+    struct Upstream {
+        fail: bool,
+        sent: AtomicU32,
+    }
+    impl RestClient for Upstream {
+        type Error = ();
+
+        fn rest_endpoint(&self, _: &RequestTarget<'_>) -> Result<Uri, APIError<()>> {
+            unreachable!()
+        }
+    }
+    impl AsyncClient for Upstream {
+        async fn rest_async(
+            &self,
+            request: Request<Vec<u8>>,
+        ) -> Result<Response<Bytes>, APIError<()>> {
+            self.sent.fetch_add(1, Ordering::Relaxed);
+            let mut yielded = false;
+            poll_fn(|cx| {
+                if yielded {
+                    return Poll::Ready(());
+                }
+                yielded = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            })
+            .await;
+
+            if self.fail {
+                Err(APIErrorKind::Timeout(Duration::from_secs(1)))?
+            }
+            Ok(Response::new(Bytes::from(request.uri().path().to_string())))
+        }
+    }
+
+    fn client(fail: bool) -> CoalescingClient<Upstream> {
+        CoalescingClient::new(Upstream {
+            fail,
+            sent: AtomicU32::new(0),
+        })
+    }
+
+    fn request(method: Method, path: &str) -> Request<Vec<u8>> {
+        Request::builder()
+            .method(method)
+            .uri(format!("https://example.com{path}"))
+            .body(Vec::new())
+            .unwrap()
+    }
+
+    fn run<T>(future: impl Future<Output = T>) -> T {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn collapses_identical_requests() {
+        let client = client(false);
+        let responses = run(join_all([
+            client.rest_async(request(Method::GET, "/pets")),
+            client.rest_async(request(Method::GET, "/pets")),
+            client.rest_async(request(Method::GET, "/pets")),
+            client.rest_async(request(Method::GET, "/toys")),
+        ]));
+        let bodies: Vec<_> = responses
+            .into_iter()
+            .map(|x| x.unwrap().into_body())
+            .collect();
+        assert_eq!(bodies, ["/pets", "/pets", "/pets", "/toys"]);
+        assert_eq!(client.inner().sent.load(Ordering::Relaxed), 2);
+
+        // Requests which may change something are always sent
+        let responses = run(join_all([
+            client.rest_async(request(Method::POST, "/pets")),
+            client.rest_async(request(Method::POST, "/pets")),
+        ]));
+        assert!(responses.iter().all(Result::is_ok));
+        assert_eq!(client.inner().sent.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn shares_errors() {
+        let client = client(true);
+        let errors = run(join_all([
+            client.rest_async(request(Method::GET, "/pets")),
+            client.rest_async(request(Method::GET, "/pets")),
+            client.rest_async(request(Method::GET, "/pets")),
+        ]));
+        assert_eq!(client.inner().sent.load(Ordering::Relaxed), 1);
+        for error in errors {
+            let error = error.unwrap_err();
+            let APIErrorKind::Shared(shared) = error.kind() else {
+                panic!("expected a shared error");
+            };
+            assert!(matches!(shared.kind(), APIErrorKind::Timeout(_)));
+        }
+
+        // Without waiters, the error is returned as it is
+        let error = run(client.rest_async(request(Method::GET, "/pets"))).unwrap_err();
+        assert!(matches!(error.kind(), APIErrorKind::Timeout(_)));
+    }
+}
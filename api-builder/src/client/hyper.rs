@@ -0,0 +1,129 @@
+use core::future::Future;
+
+use bytes::Bytes;
+use http::{Request, Response};
+use http_body_util::{BodyExt, Full};
+use hyper_util::client::legacy::{Client, connect::Connect};
+
+use crate::{APIError, APIErrorKind, HttpClientError, RequestTimeout, RestClient};
+
+/// The hyper client type used by [`HyperAsyncClient`].
+pub type HyperClient<C> = Client<C, Full<Bytes>>;
+
+/// A trait representing a client which includes a hyper client.
+///
+/// Unlike [`ReqwestAsyncClient`](crate::ReqwestAsyncClient), this does not implement [`AsyncClient`](crate::AsyncClient) on its own.
+/// Forward [`AsyncClient::rest_async`](crate::AsyncClient::rest_async) to [`HyperAsyncClient::rest_hyper`], or use `#[derive(HyperAsyncClient)]` which does both.
+pub trait HyperAsyncClient: RestClient {
+    /// The connector used by the hyper client.
+    type Connector: Connect + Clone + Send + Sync + 'static;
+
+    /// Get the hyper client.
+    fn client(&self) -> &HyperClient<Self::Connector>;
+
+    /// Send a REST query asynchronously with the hyper client.
+    fn rest_hyper(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> impl Future<Output = Result<Response<Bytes>, APIError<Self::Error>>> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            let timeout = RequestTimeout::get(&request);
+            let send = async {
+                // Send the request
+                let response = self
+                    .client()
+                    .request(request.map(|body| Full::new(Bytes::from(body))))
+                    .await
+                    .map_err(HttpClientError::from)?;
+
+                // Collect the body and return the response
+                let (parts, body) = response.into_parts();
+                let body = body
+                    .collect()
+                    .await
+                    .map_err(HttpClientError::from)?
+                    .to_bytes();
+                Ok(Response::from_parts(parts, body))
+            };
+
+            // hyper has no timeouts of its own, so the whole exchange is limited
+            match timeout {
+                Some(timeout) => tokio::time::timeout(timeout, send)
+                    .await
+                    .map_err(|_| APIErrorKind::Timeout(timeout))?,
+                None => send.await,
+            }
+        }
+    }
+}
+
+/// Create a hyper client for plain HTTP, using the tokio runtime.
+pub fn hyper_http_client() -> HyperClient<hyper_util::client::legacy::connect::HttpConnector> {
+    Client::builder(hyper_util::rt::TokioExecutor::new()).build_http()
+}
+
+/// Create a hyper client for HTTPS (and HTTP) using rustls with the webpki roots, using the tokio runtime.
+#[cfg(feature = "hyper-rustls")]
+pub fn hyper_rustls_client() -> HyperClient<
+    hyper_rustls::HttpsConnector<hyper_util::client::legacy::connect::HttpConnector>,
+> {
+    let connector = hyper_rustls::HttpsConnectorBuilder::new()
+        .with_webpki_roots()
+        .https_or_http()
+        .enable_http1()
+        .enable_http2()
+        .build();
+    Client::builder(hyper_util::rt::TokioExecutor::new()).build(connector)
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use std::{net::TcpListener, time::Duration};
+
+    use http::Uri;
+    use hyper_util::client::legacy::connect::HttpConnector;
+
+    use super::*;
+    use crate::{AsyncClient, RequestTarget};
+
+    /// A client for a server which never answers.
+    #[derive(crate::HyperAsyncClient)]
+    struct Silent {
+        hyper_client: HyperClient<HttpConnector>,
+    }
+    impl RestClient for Silent {
+        type Error = ();
+
+        fn rest_endpoint(&self, _: &RequestTarget<'_>) -> Result<Uri, APIError<()>> {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn applies_request_timeouts() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+
+        let error = runtime.block_on(async {
+            let client = Silent {
+                hyper_client: hyper_http_client(),
+            };
+            let request = Request::get(url)
+                .extension(RequestTimeout(Duration::from_millis(50)))
+                .body(Vec::new())
+                .unwrap();
+            client.rest_async(request).await.unwrap_err()
+        });
+        assert!(matches!(
+            error.kind(),
+            APIErrorKind::Timeout(timeout) if *timeout == Duration::from_millis(50)
+        ));
+    }
+}
@@ -1,13 +1,27 @@
 use core::future::Future;
+use std::io::Read;
 
 use bytes::Bytes;
-use http::{Request, Response};
-use url::Url;
+use http::{Request, Response, Uri};
 
-use crate::APIError;
+use crate::{APIError, RequestBody, RequestTarget};
 
+import!(caching, coalescing, timeout);
+// Both count time with `Instant::now`, which panics on wasm
+#[cfg(not(target_family = "wasm"))]
+import!(circuit_breaker, multi_host);
+#[cfg(feature = "tokio")]
+#[cfg(not(target_family = "wasm"))]
+import!(block_on);
+#[cfg(not(target_family = "wasm"))]
+import!(spawn_blocking);
 #[cfg(feature = "reqwest")]
 import!(reqwest);
+#[cfg(feature = "hyper")]
+import!(hyper);
+#[cfg(feature = "ureq")]
+#[cfg(not(target_family = "wasm"))]
+import!(ureq);
 #[cfg(target_family = "wasm")]
 #[cfg(not(feature = "reqwest"))]
 import!(wasm);
@@ -19,14 +33,25 @@ pub trait RestClient {
 
     /// Get the URL for the endpoint for the client.
     ///
-    /// This method adds the hostname for the client's target instance.
-    fn rest_endpoint(&self, path: &str) -> Result<Url, APIError<Self::Error>>;
+    /// This method adds the hostname for the client's target instance, usually with [`RequestTarget::join`].
+    fn rest_endpoint(&self, target: &RequestTarget<'_>) -> Result<Uri, APIError<Self::Error>>;
 }
 
 /// A trait representing a client.
 pub trait Client: RestClient {
     /// Send a REST query.
     fn rest(&self, request: Request<Vec<u8>>) -> Result<Response<Bytes>, APIError<Self::Error>>;
+
+    /// Send a REST query with a [`RequestBody`].
+    ///
+    /// Defaults to reading the body into memory and using [`Client::rest`].
+    fn rest_body(
+        &self,
+        request: Request<RequestBody>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let (parts, body) = request.into_parts();
+        self.rest(Request::from_parts(parts, body.into_bytes()?))
+    }
 }
 
 /// A trait representing an asynchronous client.
@@ -44,4 +69,90 @@ pub trait AsyncClient: RestClient {
         &self,
         request: Request<Vec<u8>>,
     ) -> impl Future<Output = Result<Response<Bytes>, APIError<Self::Error>>>;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    /// Send a REST query asynchronously with a [`RequestBody`].
+    ///
+    /// Defaults to reading the body into memory and using [`AsyncClient::rest_async`].
+    fn rest_body_async(
+        &self,
+        request: Request<RequestBody>,
+    ) -> impl Future<Output = Result<Response<Bytes>, APIError<Self::Error>>> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            let (parts, body) = request.into_parts();
+            self.rest_async(Request::from_parts(parts, body.into_bytes_async().await?))
+                .await
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    /// Send a REST query asynchronously with a [`RequestBody`].
+    ///
+    /// Defaults to reading the body into memory and using [`AsyncClient::rest_async`].
+    fn rest_body_async(
+        &self,
+        request: Request<RequestBody>,
+    ) -> impl Future<Output = Result<Response<Bytes>, APIError<Self::Error>>> {
+        async move {
+            let (parts, body) = request.into_parts();
+            self.rest_async(Request::from_parts(parts, body.into_bytes_async().await?))
+                .await
+        }
+    }
 }
+
+/// A trait representing a client which can stream response bodies.
+///
+/// Unlike [`Client::rest`], the body is not buffered into memory.
+pub trait StreamingClient: RestClient {
+    /// The response body reader.
+    type Body: Read;
+
+    /// Send a REST query, returning the response body as a reader.
+    fn rest_stream(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<Response<Self::Body>, APIError<Self::Error>>;
+}
+
+/// A trait representing an asynchronous client which can stream response bodies.
+///
+/// Unlike [`AsyncClient::rest_async`], the body is not buffered into memory.
+#[cfg(feature = "stream")]
+pub trait AsyncStreamingClient: RestClient {
+    /// The response body stream.
+    #[cfg(not(target_arch = "wasm32"))]
+    type Stream: futures_core::Stream<Item = Result<Bytes, APIError<Self::Error>>> + Send + Unpin;
+    /// The response body stream.
+    #[cfg(target_arch = "wasm32")]
+    type Stream: futures_core::Stream<Item = Result<Bytes, APIError<Self::Error>>> + Unpin;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    /// Send a REST query asynchronously, returning the response body as a stream.
+    fn rest_stream_async(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> impl Future<Output = Result<Response<Self::Stream>, APIError<Self::Error>>> + Send;
+
+    #[cfg(target_arch = "wasm32")]
+    /// Send a REST query asynchronously, returning the response body as a stream.
+    fn rest_stream_async(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> impl Future<Output = Result<Response<Self::Stream>, APIError<Self::Error>>>;
+}
+
+/// A boxed stream of response body chunks.
+#[cfg(feature = "stream")]
+#[cfg(not(target_arch = "wasm32"))]
+pub type BytesStream<E> = core::pin::Pin<
+    Box<dyn futures_core::Stream<Item = Result<Bytes, APIError<E>>> + Send + 'static>,
+>;
+/// A boxed stream of response body chunks.
+#[cfg(feature = "stream")]
+#[cfg(target_arch = "wasm32")]
+pub type BytesStream<E> =
+    core::pin::Pin<Box<dyn futures_core::Stream<Item = Result<Bytes, APIError<E>>> + 'static>>;
@@ -0,0 +1,502 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::BuildHasher,
+    sync::{
+        Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use http::{Method, Request, Response, StatusCode, Uri};
+use url::{Position, Url};
+
+use crate::{
+    APIError, AsyncClient, Attempt, BaseUrl, BaseUrlError, Client, Clock, ErrorContext,
+    RequestBody, RequestTarget, RestClient, SystemClock,
+};
+
+/// How a [`MultiHostClient`] picks the host for each request.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub enum HostSelection {
+    /// Each request starts at the next host.
+    #[default]
+    RoundRobin,
+    /// Each request starts at a random host.
+    Random,
+    /// Each request starts at the first healthy host, in the order they were given.
+    Priority,
+}
+
+/// A client which spreads requests over several hosts serving the same API.
+///
+/// URLs are built against the first host, then moved to the host picked by [`HostSelection`].
+/// Only the scheme, authority and path of each base URL are used, any query of the first host is kept.
+///
+/// Connection failures, timeouts and responses with a failover status (`502`, `503` and `504` by default) mark a host unhealthy for the cool-down, and it is skipped until then.
+/// Idempotent requests are then retried on the next host, up to `max_attempts` hosts.
+/// If every host is unhealthy, they are all tried anyway.
+pub struct MultiHostClient<C, K = SystemClock> {
+    client: C,
+    clock: K,
+    hosts: Vec<BaseUrl>,
+    selection: HostSelection,
+    max_attempts: usize,
+    cool_down: Duration,
+    failover_statuses: Vec<StatusCode>,
+    next: AtomicUsize,
+    seed: AtomicU64,
+    unhealthy: Mutex<Vec<Option<Instant>>>,
+}
+impl<C> MultiHostClient<C> {
+    /// Wrap `client`, sending requests to `hosts`.
+    ///
+    /// Defaults to round-robin, trying every host, with a cool-down of 30 seconds.
+    pub fn new(client: C, hosts: impl IntoIterator<Item = BaseUrl>) -> Self {
+        let hosts: Vec<_> = hosts.into_iter().collect();
+        Self {
+            client,
+            clock: SystemClock,
+            selection: HostSelection::RoundRobin,
+            max_attempts: hosts.len(),
+            cool_down: Duration::from_secs(30),
+            failover_statuses: vec![
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            next: AtomicUsize::new(0),
+            seed: AtomicU64::new(RandomState::new().hash_one(0u8)),
+            unhealthy: Mutex::new(vec![None; hosts.len()]),
+            hosts,
+        }
+    }
+}
+impl<C, K> MultiHostClient<C, K> {
+    /// Use a different clock.
+    pub fn clock<K2: Clock>(self, clock: K2) -> MultiHostClient<C, K2> {
+        MultiHostClient {
+            client: self.client,
+            clock,
+            hosts: self.hosts,
+            selection: self.selection,
+            max_attempts: self.max_attempts,
+            cool_down: self.cool_down,
+            failover_statuses: self.failover_statuses,
+            next: self.next,
+            seed: self.seed,
+            unhealthy: self.unhealthy,
+        }
+    }
+
+    /// How the host for each request is picked.
+    pub fn selection(mut self, selection: HostSelection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// The most hosts an idempotent request is sent to.
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// How long an unhealthy host is skipped for.
+    pub fn cool_down(mut self, cool_down: Duration) -> Self {
+        self.cool_down = cool_down;
+        self
+    }
+
+    /// The response statuses which mark a host unhealthy and are retried on the next host.
+    pub fn failover_statuses(mut self, statuses: impl IntoIterator<Item = StatusCode>) -> Self {
+        self.failover_statuses = statuses.into_iter().collect();
+        self
+    }
+
+    /// Get the inner client.
+    pub fn inner(&self) -> &C {
+        &self.client
+    }
+
+    /// The hosts requests are sent to.
+    pub fn hosts(&self) -> &[BaseUrl] {
+        &self.hosts
+    }
+
+    fn unhealthy(&self) -> MutexGuard<'_, Vec<Option<Instant>>> {
+        self.unhealthy
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// A pseudo-random number, from a randomly seeded SplitMix64.
+    fn random(&self) -> u64 {
+        let mut x = self
+            .seed
+            .fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed)
+            .wrapping_add(0x9E37_79B9_7F4A_7C15);
+        x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        x ^ (x >> 31)
+    }
+
+    /// The part of `url` which a request URL starts with.
+    fn prefix(url: &BaseUrl) -> &str {
+        url.url()[..Position::AfterPath].trim_end_matches('/')
+    }
+
+    /// The part of `uri` after the first host, if it was built against it.
+    fn suffix(&self, uri: &Uri) -> Option<String> {
+        let prefix = Self::prefix(self.hosts.first()?);
+        // Compare as parsed URLs, so the default port, case and escaping are written like the host's
+        let url = Url::parse(&uri.to_string()).ok()?;
+        let suffix = url.as_str().strip_prefix(prefix)?;
+        matches!(suffix.chars().next(), None | Some('/' | '?')).then(|| suffix.to_owned())
+    }
+
+    /// Move `request` to `host`.
+    fn request_for<T, E>(
+        &self,
+        mut request: Request<T>,
+        suffix: &str,
+        host: usize,
+    ) -> Result<Request<T>, APIError<E>> {
+        let uri = format!("{}{suffix}", Self::prefix(&self.hosts[host]));
+        *request.uri_mut() = Uri::try_from(uri).map_err(http::Error::from)?;
+        Ok(request)
+    }
+}
+impl<C, K> MultiHostClient<C, K>
+where
+    K: Clock,
+{
+    /// Whether a host is being sent requests, see [`MultiHostClient::hosts`].
+    pub fn is_healthy(&self, host: usize) -> bool {
+        let now = self.clock.now();
+        self.unhealthy()
+            .get(host)
+            .is_some_and(|until| until.is_none_or(|until| now >= until))
+    }
+
+    /// The hosts to try for a request, in order.
+    fn plan(&self, method: &Method) -> Vec<usize> {
+        let now = self.clock.now();
+        let unhealthy = self.unhealthy();
+        let mut hosts: Vec<_> = (0..self.hosts.len())
+            .filter(|&i| unhealthy[i].is_none_or(|until| now >= until))
+            .collect();
+        drop(unhealthy);
+        if hosts.is_empty() {
+            hosts = (0..self.hosts.len()).collect();
+        }
+
+        let start = match self.selection {
+            HostSelection::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed),
+            HostSelection::Random => self.random() as usize,
+            HostSelection::Priority => 0,
+        };
+        if let Some(start) = start.checked_rem(hosts.len()) {
+            hosts.rotate_left(start);
+        }
+
+        // Other requests may have side effects, so are only sent once
+        hosts.truncate(if method.is_idempotent() {
+            self.max_attempts
+        } else {
+            1
+        });
+        hosts
+    }
+
+    /// Record the outcome of the `attempt`th request, to `uri` on `host`, returning whether it failed.
+    fn record<E>(
+        &self,
+        host: usize,
+        attempt: usize,
+        uri: &Uri,
+        result: &mut Result<Response<Bytes>, APIError<E>>,
+    ) -> bool {
+        let number = attempt as u32;
+        let failure = match result {
+            Ok(response) => {
+                response.extensions_mut().insert(Attempt {
+                    number,
+                    uri: uri.clone(),
+                });
+                self.failover_statuses.contains(&response.status())
+            }
+            Err(e) => {
+                let context = e.context_mut();
+                context.attempt = Some(number);
+                context.url = Some(ErrorContext::redact_url(uri));
+                // Other errors would happen on any host
+                e.is_connect() || e.is_timeout()
+            }
+        };
+        self.unhealthy()[host] = failure.then(|| self.clock.now() + self.cool_down);
+        failure
+    }
+}
+
+impl<C, K> RestClient for MultiHostClient<C, K>
+where
+    C: RestClient,
+{
+    type Error = C::Error;
+
+    fn rest_endpoint(&self, target: &RequestTarget<'_>) -> Result<Uri, APIError<Self::Error>> {
+        let host = self.hosts.first().ok_or(BaseUrlError::NoHosts)?;
+        Ok(host.join(target)?)
+    }
+}
+impl<C, K> Client for MultiHostClient<C, K>
+where
+    C: Client,
+    K: Clock,
+{
+    fn rest(&self, request: Request<Vec<u8>>) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let Some(suffix) = self.suffix(request.uri()) else {
+            return self.client.rest(request);
+        };
+        let hosts = self.plan(request.method());
+        let Some((&last, hosts)) = hosts.split_last() else {
+            return self.client.rest(request);
+        };
+
+        for (i, &host) in hosts.iter().enumerate() {
+            let request = self.request_for(request.clone(), &suffix, host)?;
+            let uri = request.uri().clone();
+            let mut result = self.client.rest(request);
+            if !self.record(host, i + 1, &uri, &mut result) {
+                return result;
+            }
+        }
+        let request = self.request_for(request, &suffix, last)?;
+        let uri = request.uri().clone();
+        let mut result = self.client.rest(request);
+        self.record(last, hosts.len() + 1, &uri, &mut result);
+        result
+    }
+
+    /// The body can't be sent twice, so the request is only sent to one host.
+    fn rest_body(
+        &self,
+        request: Request<RequestBody>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let (Some(suffix), Some(&host)) = (
+            self.suffix(request.uri()),
+            self.plan(request.method()).first(),
+        ) else {
+            return self.client.rest_body(request);
+        };
+
+        let request = self.request_for(request, &suffix, host)?;
+        let uri = request.uri().clone();
+        let mut result = self.client.rest_body(request);
+        self.record(host, 1, &uri, &mut result);
+        result
+    }
+}
+impl<C, K> AsyncClient for MultiHostClient<C, K>
+where
+    C: AsyncClient + Sync,
+    C::Error: Send,
+    K: Clock + Sync,
+{
+    async fn rest_async(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let Some(suffix) = self.suffix(request.uri()) else {
+            return self.client.rest_async(request).await;
+        };
+        let hosts = self.plan(request.method());
+        let Some((&last, hosts)) = hosts.split_last() else {
+            return self.client.rest_async(request).await;
+        };
+
+        for (i, &host) in hosts.iter().enumerate() {
+            let request = self.request_for(request.clone(), &suffix, host)?;
+            let uri = request.uri().clone();
+            let mut result = self.client.rest_async(request).await;
+            if !self.record(host, i + 1, &uri, &mut result) {
+                return result;
+            }
+        }
+        let request = self.request_for(request, &suffix, last)?;
+        let uri = request.uri().clone();
+        let mut result = self.client.rest_async(request).await;
+        self.record(last, hosts.len() + 1, &uri, &mut result);
+        result
+    }
+
+    /// The body can't be sent twice, so the request is only sent to one host.
+    async fn rest_body_async(
+        &self,
+        request: Request<RequestBody>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let (Some(suffix), Some(&host)) = (
+            self.suffix(request.uri()),
+            self.plan(request.method()).first(),
+        ) else {
+            return self.client.rest_body_async(request).await;
+        };
+
+        let request = self.request_for(request, &suffix, host)?;
+        let uri = request.uri().clone();
+        let mut result = self.client.rest_body_async(request).await;
+        self.record(host, 1, &uri, &mut result);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    /// A clock which only moves when told to.
+    struct ManualClock(Mutex<Instant>);
+    impl Clock for ManualClock {
+        fn now(&self) -> Instant {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    /// Records every request, answering `503` from the hosts which are down.
+    #[derive(Default)]
+    struct Hosts {
+        down: Mutex<Vec<&'static str>>,
+        sent: Mutex<Vec<String>>,
+    }
+    impl Hosts {
+        fn sent(&self) -> Vec<String> {
+            core::mem::take(&mut *self.sent.lock().unwrap())
+        }
+    }
+    impl RestClient for Hosts {
+        type Error = ();
+
+        fn rest_endpoint(&self, _: &RequestTarget<'_>) -> Result<Uri, APIError<()>> {
+            unreachable!()
+        }
+    }
+    impl Client for Hosts {
+        fn rest(&self, request: Request<Vec<u8>>) -> Result<Response<Bytes>, APIError<()>> {
+            let uri = request.uri().to_string();
+            let host = request.uri().host().unwrap_or_default();
+            let status = if self.down.lock().unwrap().contains(&host) {
+                StatusCode::SERVICE_UNAVAILABLE
+            } else {
+                StatusCode::OK
+            };
+            self.sent.lock().unwrap().push(uri);
+            Ok(Response::builder().status(status).body(Bytes::new())?)
+        }
+    }
+
+    type Multi = MultiHostClient<Hosts, Arc<ManualClock>>;
+
+    fn multi_host(selection: HostSelection, down: &[&'static str]) -> (Multi, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock(Mutex::new(Instant::now())));
+        let hosts = ["https://a.example.com/v1", "https://b.example.com/v2/"]
+            .map(|x| BaseUrl::parse(x).unwrap());
+        let client = MultiHostClient::new(
+            Hosts {
+                down: Mutex::new(down.to_vec()),
+                ..Default::default()
+            },
+            hosts,
+        )
+        .selection(selection)
+        .cool_down(Duration::from_secs(30))
+        .clock(clock.clone());
+        (client, clock)
+    }
+
+    fn send(client: &Multi, method: Method, uri: &str) -> Response<Bytes> {
+        let request = Request::builder().method(method).uri(uri);
+        client.rest(request.body(Vec::new()).unwrap()).unwrap()
+    }
+
+    const PETS: &str = "https://a.example.com/v1/pets?page=2";
+    const A: &str = "https://a.example.com/v1/pets?page=2";
+    const B: &str = "https://b.example.com/v2/pets?page=2";
+
+    #[test]
+    fn fails_over_until_the_cool_down_passes() {
+        let (client, clock) = multi_host(HostSelection::Priority, &["a.example.com"]);
+        let response = send(&client, Method::GET, PETS);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.extensions().get::<Attempt>().unwrap().number, 2);
+        assert_eq!(client.inner().sent(), [A, B]);
+        assert!(!client.is_healthy(0));
+
+        // The unhealthy host is skipped until the cool-down has passed
+        client.inner().down.lock().unwrap().clear();
+        send(&client, Method::GET, PETS);
+        assert_eq!(client.inner().sent(), [B]);
+        *clock.0.lock().unwrap() += Duration::from_secs(30);
+        assert!(client.is_healthy(0));
+        send(&client, Method::GET, PETS);
+        assert_eq!(client.inner().sent(), [A]);
+    }
+
+    #[test]
+    fn tries_every_host_when_all_are_unhealthy() {
+        let (client, _) = multi_host(HostSelection::Priority, &["a.example.com", "b.example.com"]);
+        for _ in 0..2 {
+            let response = send(&client, Method::GET, PETS);
+            assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+            assert_eq!(client.inner().sent(), [A, B]);
+        }
+    }
+
+    #[test]
+    fn picks_hosts_in_order() {
+        let (client, _) = multi_host(HostSelection::RoundRobin, &[]);
+        for _ in 0..4 {
+            send(&client, Method::GET, PETS);
+        }
+        assert_eq!(client.inner().sent(), [A, B, A, B]);
+
+        let (client, _) = multi_host(HostSelection::Priority, &[]);
+        for _ in 0..2 {
+            send(&client, Method::GET, PETS);
+        }
+        assert_eq!(client.inner().sent(), [A, A]);
+    }
+
+    #[test]
+    fn sends_other_requests_once() {
+        let (client, _) = multi_host(HostSelection::Priority, &["a.example.com"]);
+        let response = send(&client, Method::POST, PETS);
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(client.inner().sent(), [A]);
+
+        // The failure still marks the host unhealthy
+        send(&client, Method::POST, PETS);
+        assert_eq!(client.inner().sent(), [B]);
+    }
+
+    #[test]
+    fn retargets_urls_written_differently() {
+        let (client, _) = multi_host(HostSelection::Priority, &["a.example.com"]);
+        send(
+            &client,
+            Method::GET,
+            "https://A.example.com:443/v1/pets?page=2",
+        );
+        assert_eq!(
+            client.inner().sent(),
+            ["https://a.example.com/v1/pets?page=2", B]
+        );
+
+        // Requests for another path are sent as they are
+        let other = "https://a.example.com/v10/pets";
+        send(&client, Method::GET, other);
+        assert_eq!(client.inner().sent(), [other]);
+    }
+}
@@ -1,8 +1,18 @@
+use core::time::Duration;
+
 use bytes::Bytes;
 use http::{Request, Response};
 
 use crate::RestClient;
-use crate::{APIError, AsyncClient};
+use crate::{APIError, APIErrorKind, AsyncClient, RequestBody, RequestTimeout};
+
+/// Maps reqwest timeouts caused by a [`RequestTimeout`] to [`APIErrorKind::Timeout`].
+fn map_error<E>(error: reqwest::Error, timeout: Option<Duration>) -> APIError<E> {
+    match timeout {
+        Some(timeout) if error.is_timeout() => APIErrorKind::Timeout(timeout).into(),
+        _ => error.into(),
+    }
+}
 
 /// A trait represnting a client which includes a reqwest client.
 #[cfg(feature = "reqwest")]
@@ -11,6 +21,39 @@ pub trait ReqwestAsyncClient: RestClient {
     fn client(&self) -> &reqwest::Client;
 }
 
+/// Converts the request, applying any [`RequestTimeout`].
+#[cfg(feature = "reqwest")]
+fn into_reqwest<T>(request: Request<T>) -> Result<reqwest::Request, reqwest::Error>
+where
+    T: Into<reqwest::Body>,
+{
+    let timeout = RequestTimeout::get(&request);
+    let mut request: reqwest::Request = request.try_into()?;
+    if timeout.is_some() {
+        *request.timeout_mut() = timeout;
+    }
+    Ok(request)
+}
+
+/// Builds the response head from a reqwest response.
+#[cfg(feature = "reqwest")]
+fn response_builder(response: &reqwest::Response) -> http::response::Builder {
+    // Construct the response builder
+    #[allow(unused_mut)]
+    let mut http_response = Response::builder().status(response.status());
+
+    #[cfg(not(target_family = "wasm"))]
+    let mut http_response = http_response.version(response.version());
+
+    // Add headers
+    let headers = http_response.headers_mut().unwrap();
+    for (key, value) in response.headers() {
+        headers.append(key, value.clone());
+    }
+
+    http_response
+}
+
 #[cfg(feature = "reqwest")]
 impl<C> AsyncClient for C
 where
@@ -21,23 +64,89 @@ where
         request: Request<Vec<u8>>,
     ) -> Result<Response<Bytes>, APIError<Self::Error>> {
         // Send the request
-        let response = self.client().execute(request.try_into()?).await?;
+        let timeout = RequestTimeout::get(&request);
+        let response = self
+            .client()
+            .execute(into_reqwest(request)?)
+            .await
+            .map_err(|e| map_error(e, timeout))?;
 
-        // Construct the response builder
+        // Add the body and return the response
+        let http_response = response_builder(&response);
+        let body = response.bytes().await.map_err(|e| map_error(e, timeout))?;
+        Ok(http_response.body(body)?)
+    }
+
+    async fn rest_body_async(
+        &self,
+        request: Request<RequestBody>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let timeout = RequestTimeout::get(&request);
         #[allow(unused_mut)]
-        let mut http_response = Response::builder().status(response.status());
+        let (mut parts, body) = request.into_parts();
 
-        #[cfg(not(target_family = "wasm"))]
-        let mut http_response = http_response.version(response.version());
+        // Stream the body when possible, otherwise buffer it
+        #[cfg(all(feature = "stream", not(target_arch = "wasm32")))]
+        let body = match body {
+            RequestBody::Bytes(body) => reqwest::Body::from(body),
+            body => {
+                if let Some(length) = body.len() {
+                    parts
+                        .headers
+                        .entry(http::header::CONTENT_LENGTH)
+                        .or_insert(length.into());
+                }
+                reqwest::Body::wrap_stream(body.into_stream()?)
+            }
+        };
+        #[cfg(not(all(feature = "stream", not(target_arch = "wasm32"))))]
+        let body = reqwest::Body::from(body.into_bytes_async().await?);
 
-        // Add headers
-        let headers = http_response.headers_mut().unwrap();
-        for (key, value) in response.headers() {
-            headers.append(key, value.clone());
-        }
+        // Send the request
+        let response = self
+            .client()
+            .execute(into_reqwest(Request::from_parts(parts, body))?)
+            .await
+            .map_err(|e| map_error(e, timeout))?;
 
         // Add the body and return the response
-        Ok(http_response.body(response.bytes().await?)?)
+        let http_response = response_builder(&response);
+        let body = response.bytes().await.map_err(|e| map_error(e, timeout))?;
+        Ok(http_response.body(body)?)
+    }
+}
+
+#[cfg(feature = "reqwest")]
+#[cfg(feature = "stream")]
+impl<C> crate::AsyncStreamingClient for C
+where
+    C: ReqwestAsyncClient + Sync,
+    C::Error: Send + 'static,
+{
+    type Stream = crate::BytesStream<C::Error>;
+
+    async fn rest_stream_async(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<Response<Self::Stream>, APIError<Self::Error>> {
+        use futures_util::StreamExt;
+
+        // Send the request
+        let timeout = RequestTimeout::get(&request);
+        let response = self
+            .client()
+            .execute(into_reqwest(request)?)
+            .await
+            .map_err(|e| map_error(e, timeout))?;
+
+        // Add the body stream and return the response
+        let http_response = response_builder(&response);
+        let stream: Self::Stream = Box::pin(
+            response
+                .bytes_stream()
+                .map(move |chunk| chunk.map_err(|e| map_error(e, timeout))),
+        );
+        Ok(http_response.body(stream)?)
     }
 }
 
@@ -51,6 +160,39 @@ pub trait ReqwestClient: RestClient {
     fn client(&self) -> &reqwest::blocking::Client;
 }
 
+/// Converts the request, applying any [`RequestTimeout`].
+#[cfg(feature = "reqwest_blocking")]
+#[cfg(not(target_family = "wasm"))]
+fn into_blocking_reqwest<T>(request: Request<T>) -> Result<reqwest::blocking::Request, reqwest::Error>
+where
+    T: Into<reqwest::blocking::Body>,
+{
+    let timeout = RequestTimeout::get(&request);
+    let mut request: reqwest::blocking::Request = request.try_into()?;
+    if timeout.is_some() {
+        *request.timeout_mut() = timeout;
+    }
+    Ok(request)
+}
+
+/// Builds the response head from a blocking reqwest response.
+#[cfg(feature = "reqwest_blocking")]
+#[cfg(not(target_family = "wasm"))]
+fn blocking_response_builder(response: &reqwest::blocking::Response) -> http::response::Builder {
+    // Construct the response builder
+    let mut http_response = Response::builder()
+        .status(response.status())
+        .version(response.version());
+
+    // Add headers
+    let headers = http_response.headers_mut().unwrap();
+    for (key, value) in response.headers() {
+        headers.append(key, value.clone());
+    }
+
+    http_response
+}
+
 #[cfg(feature = "reqwest_blocking")]
 #[cfg(not(target_family = "wasm"))]
 impl<C> crate::Client for C
@@ -59,20 +201,79 @@ where
 {
     fn rest(&self, request: Request<Vec<u8>>) -> Result<Response<Bytes>, APIError<Self::Error>> {
         // Send the request
-        let response = self.client().execute(request.try_into()?)?;
+        let timeout = RequestTimeout::get(&request);
+        let response = self
+            .client()
+            .execute(into_blocking_reqwest(request)?)
+            .map_err(|e| map_error(e, timeout))?;
 
-        // Construct the response builder
-        let mut http_response = Response::builder()
-            .status(response.status())
-            .version(response.version());
+        // Add the body and return the response
+        let http_response = blocking_response_builder(&response);
+        let body = response.bytes().map_err(|e| map_error(e, timeout))?;
+        Ok(http_response.body(body)?)
+    }
 
-        // Add headers
-        let headers = http_response.headers_mut().unwrap();
-        for (key, value) in response.headers() {
-            headers.append(key, value.clone());
-        }
+    fn rest_body(
+        &self,
+        request: Request<RequestBody>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        use reqwest::blocking::Body;
+
+        let timeout = RequestTimeout::get(&request);
+        let (parts, body) = request.into_parts();
+        let body = match body {
+            RequestBody::Bytes(body) => Body::from(body),
+            RequestBody::File(path) => {
+                Body::from(std::fs::File::open(path).map_err(crate::BodyError::from)?)
+            }
+            RequestBody::Reader {
+                reader,
+                length: Some(length),
+            } => Body::sized(reader, length),
+            RequestBody::Reader {
+                reader,
+                length: None,
+            } => Body::new(reader),
+            #[cfg(feature = "stream")]
+            RequestBody::AsyncReader { .. } | RequestBody::Stream { .. } => {
+                Err(crate::BodyError::AsyncOnly)?
+            }
+        };
+
+        // Send the request
+        let response = self
+            .client()
+            .execute(into_blocking_reqwest(Request::from_parts(parts, body))?)
+            .map_err(|e| map_error(e, timeout))?;
 
         // Add the body and return the response
-        Ok(http_response.body(response.bytes()?)?)
+        let http_response = blocking_response_builder(&response);
+        let body = response.bytes().map_err(|e| map_error(e, timeout))?;
+        Ok(http_response.body(body)?)
+    }
+}
+
+#[cfg(feature = "reqwest_blocking")]
+#[cfg(not(target_family = "wasm"))]
+impl<C> crate::StreamingClient for C
+where
+    C: RestClient + ReqwestClient,
+{
+    type Body = reqwest::blocking::Response;
+
+    fn rest_stream(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<Response<Self::Body>, APIError<Self::Error>> {
+        // Send the request
+        let timeout = RequestTimeout::get(&request);
+        let response = self
+            .client()
+            .execute(into_blocking_reqwest(request)?)
+            .map_err(|e| map_error(e, timeout))?;
+
+        // The reqwest response is the body reader
+        let http_response = blocking_response_builder(&response);
+        Ok(http_response.body(response)?)
     }
 }
@@ -0,0 +1,248 @@
+use core::{
+    future::poll_fn,
+    task::{Poll, Waker},
+};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use bytes::Bytes;
+use http::{Request, Response, Uri};
+
+use crate::{APIError, APIErrorKind, AsyncClient, Client, RequestBody, RequestTarget, RestClient};
+
+/// A task which blocks the thread it runs on.
+pub type BlockingTask = Box<dyn FnOnce() + Send + 'static>;
+
+/// Runs [`BlockingTask`]s on a thread where blocking is allowed, e.g. a thread pool.
+///
+/// Implemented for any `Fn(BlockingTask)`, see [`TokioSpawner`] for `tokio`.
+pub trait Spawner {
+    /// Run `task` on another thread.
+    ///
+    /// If `task` is dropped without running, the request fails.
+    fn spawn(&self, task: BlockingTask);
+}
+impl<F> Spawner for F
+where
+    F: Fn(BlockingTask),
+{
+    fn spawn(&self, task: BlockingTask) {
+        self(task)
+    }
+}
+
+/// Runs tasks with `tokio::task::spawn_blocking` on the current runtime.
+///
+/// Outside of a runtime, the request fails instead of panicking.
+#[cfg(feature = "tokio")]
+#[derive(Copy, Clone, Default, Debug)]
+pub struct TokioSpawner;
+#[cfg(feature = "tokio")]
+impl Spawner for TokioSpawner {
+    fn spawn(&self, task: BlockingTask) {
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            handle.spawn_blocking(task);
+        }
+    }
+}
+
+/// The result of a task, shared with the future waiting for it.
+struct Task<T> {
+    result: Option<T>,
+    finished: bool,
+    waker: Option<Waker>,
+}
+type SharedTask<T> = Arc<Mutex<Task<T>>>;
+fn lock<T>(task: &SharedTask<T>) -> MutexGuard<'_, Task<T>> {
+    task.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Marks the task as finished when dropped, even if it panicked or never ran.
+struct Finish<T>(SharedTask<T>);
+impl<T> Drop for Finish<T> {
+    fn drop(&mut self) {
+        let mut task = lock(&self.0);
+        task.finished = true;
+        if let Some(waker) = task.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// A client which drives any blocking [`Client`] as an [`AsyncClient`].
+///
+/// Each request runs [`Client::rest`] on a thread from `spawner`, so it does not block the async runtime.
+#[derive(Debug)]
+pub struct SpawnBlocking<C, S> {
+    client: Arc<C>,
+    spawner: S,
+}
+impl<C, S> SpawnBlocking<C, S> {
+    /// Wrap `client`, using `spawner` to run requests.
+    pub fn new(client: C, spawner: S) -> Self {
+        Self {
+            client: Arc::new(client),
+            spawner,
+        }
+    }
+
+    /// Get the inner client.
+    pub fn inner(&self) -> &C {
+        &self.client
+    }
+}
+#[cfg(feature = "tokio")]
+impl<C> SpawnBlocking<C, TokioSpawner> {
+    /// Wrap `client`, using `tokio::task::spawn_blocking` to run requests.
+    pub fn tokio(client: C) -> Self {
+        Self::new(client, TokioSpawner)
+    }
+}
+impl<C, S> SpawnBlocking<C, S>
+where
+    C: Client + Send + Sync + 'static,
+    C::Error: Send + Sync + 'static,
+    S: Spawner,
+{
+    /// Run `f` with the client using the spawner, and wait for the result.
+    async fn run<T>(
+        &self,
+        f: impl FnOnce(&C) -> Result<T, APIError<C::Error>> + Send + 'static,
+    ) -> Result<T, APIError<C::Error>>
+    where
+        T: Send + 'static,
+    {
+        let task: SharedTask<Result<T, APIError<C::Error>>> = Arc::new(Mutex::new(Task {
+            result: None,
+            finished: false,
+            waker: None,
+        }));
+
+        // Spawn the task, publishing the result before marking it finished
+        let client = self.client.clone();
+        let finish = Finish(task.clone());
+        self.spawner.spawn(Box::new(move || {
+            let result = f(&client);
+            lock(&finish.0).result = Some(result);
+            drop(finish);
+        }));
+
+        // Wait for the task
+        poll_fn(|cx| {
+            let mut task = lock(&task);
+            if let Some(result) = task.result.take() {
+                return Poll::Ready(result);
+            }
+            if task.finished {
+                return Poll::Ready(Err(APIErrorKind::Other(anyhow::anyhow!(
+                    "the blocking task did not complete"
+                ))
+                .into()));
+            }
+            task.waker = Some(cx.waker().clone());
+            Poll::Pending
+        })
+        .await
+    }
+}
+impl<C, S> RestClient for SpawnBlocking<C, S>
+where
+    C: RestClient,
+{
+    type Error = C::Error;
+
+    fn rest_endpoint(&self, target: &RequestTarget<'_>) -> Result<Uri, APIError<Self::Error>> {
+        self.client.rest_endpoint(target)
+    }
+}
+impl<C, S> AsyncClient for SpawnBlocking<C, S>
+where
+    C: Client + Send + Sync + 'static,
+    C::Error: Send + Sync + 'static,
+    S: Spawner + Sync,
+{
+    async fn rest_async(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        self.run(move |client| client.rest(request)).await
+    }
+
+    async fn rest_body_async(
+        &self,
+        request: Request<RequestBody>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        // Asynchronous bodies can't be read by a blocking client, so read them here
+        #[cfg(feature = "stream")]
+        let request = match request.body() {
+            RequestBody::AsyncReader { .. } | RequestBody::Stream { .. } => {
+                let (parts, body) = request.into_parts();
+                Request::from_parts(parts, RequestBody::Bytes(body.into_bytes_async().await?))
+            }
+            _ => request,
+        };
+
+        self.run(move |client| client.rest_body(request)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A server which answers with the path, and panics for `/panic`.
+    struct Server;
+    impl RestClient for Server {
+        type Error = ();
+
+        fn rest_endpoint(&self, _: &RequestTarget<'_>) -> Result<Uri, APIError<()>> {
+            unreachable!()
+        }
+    }
+    impl Client for Server {
+        fn rest(&self, request: Request<Vec<u8>>) -> Result<Response<Bytes>, APIError<()>> {
+            let path = request.uri().path();
+            assert_ne!(path, "/panic", "the server panicked");
+            Ok(Response::new(Bytes::from(path.to_string())))
+        }
+    }
+
+    /// Send a request to `path`, polling once as every spawner here finishes before returning.
+    fn send<S: Spawner + Sync>(
+        client: &SpawnBlocking<Server, S>,
+        path: &str,
+    ) -> Result<Response<Bytes>, APIError<()>> {
+        let request = Request::get(format!("https://example.com{path}"))
+            .body(Vec::new())
+            .unwrap();
+        let mut cx = core::task::Context::from_waker(Waker::noop());
+        match core::pin::pin!(client.rest_async(request)).poll(&mut cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending => panic!("the request was left waiting"),
+        }
+    }
+
+    #[test]
+    fn returns_the_inner_result() {
+        let client = SpawnBlocking::new(Server, |task: BlockingTask| {
+            std::thread::spawn(task).join().unwrap()
+        });
+        assert_eq!(send(&client, "/pets").unwrap().into_body(), "/pets");
+    }
+
+    #[test]
+    fn fails_when_the_task_does_not_complete() {
+        let client = SpawnBlocking::new(Server, |task: BlockingTask| drop(task));
+        assert!(matches!(
+            send(&client, "/pets").unwrap_err().kind(),
+            APIErrorKind::Other(_)
+        ));
+
+        let client = SpawnBlocking::new(Server, |task: BlockingTask| {
+            assert!(std::thread::spawn(task).join().is_err());
+        });
+        assert!(matches!(
+            send(&client, "/panic").unwrap_err().kind(),
+            APIErrorKind::Other(_)
+        ));
+    }
+}
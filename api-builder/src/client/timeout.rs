@@ -0,0 +1,205 @@
+use core::{future::Future, pin::pin, task::Poll, time::Duration};
+
+use bytes::Bytes;
+use http::{Request, Response, Uri};
+
+use crate::{APIError, APIErrorKind, AsyncClient, RequestBody, RequestTarget, RestClient};
+
+/// A request extension holding how long to wait for the response.
+///
+/// Added by the query helpers from [`Endpoint::timeout`](crate::Endpoint::timeout).
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct RequestTimeout(pub Duration);
+impl RequestTimeout {
+    /// Get the timeout from the request extensions.
+    pub fn get<T>(request: &Request<T>) -> Option<Duration> {
+        request.extensions().get::<Self>().map(|x| x.0)
+    }
+}
+
+/// A client which enforces [`RequestTimeout`] for any async backend.
+///
+/// Requests which take too long fail with [`APIErrorKind::Timeout`].
+/// `sleep` is used to wait, e.g. `tokio::time::sleep`, see [`TimeoutClient::tokio`].
+#[derive(Clone, Debug)]
+pub struct TimeoutClient<C, S> {
+    client: C,
+    sleep: S,
+    default_timeout: Option<Duration>,
+}
+impl<C, S> TimeoutClient<C, S> {
+    /// Wrap `client`, using `sleep` to wait.
+    pub fn new(client: C, sleep: S) -> Self {
+        Self {
+            client,
+            sleep,
+            default_timeout: None,
+        }
+    }
+
+    /// The timeout for requests without a [`RequestTimeout`].
+    pub fn default_timeout(mut self, timeout: Duration) -> Self {
+        self.default_timeout = Some(timeout);
+        self
+    }
+
+    /// Get the inner client.
+    pub fn inner(&self) -> &C {
+        &self.client
+    }
+}
+#[cfg(feature = "tokio")]
+impl<C> TimeoutClient<C, fn(Duration) -> tokio::time::Sleep> {
+    /// Wrap `client`, using `tokio` to wait.
+    pub fn tokio(client: C) -> Self {
+        Self::new(client, tokio::time::sleep)
+    }
+}
+impl<C, S, F> TimeoutClient<C, S>
+where
+    S: Fn(Duration) -> F,
+    F: Future<Output = ()>,
+{
+    /// Run `future`, failing if it does not complete within the timeout.
+    async fn run<T, E>(
+        &self,
+        timeout: Option<Duration>,
+        future: impl Future<Output = Result<T, APIError<E>>>,
+    ) -> Result<T, APIError<E>> {
+        let Some(timeout) = timeout.or(self.default_timeout) else {
+            return future.await;
+        };
+
+        let mut future = pin!(future);
+        let mut sleep = pin!((self.sleep)(timeout));
+        core::future::poll_fn(|cx| {
+            if let Poll::Ready(result) = future.as_mut().poll(cx) {
+                return Poll::Ready(result);
+            }
+            if sleep.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Err(APIErrorKind::Timeout(timeout).into()));
+            }
+            Poll::Pending
+        })
+        .await
+    }
+}
+impl<C, S> RestClient for TimeoutClient<C, S>
+where
+    C: RestClient,
+{
+    type Error = C::Error;
+
+    fn rest_endpoint(&self, target: &RequestTarget<'_>) -> Result<Uri, APIError<Self::Error>> {
+        self.client.rest_endpoint(target)
+    }
+}
+impl<C, S, F> AsyncClient for TimeoutClient<C, S>
+where
+    C: AsyncClient + Sync,
+    S: Fn(Duration) -> F + Sync,
+    F: Future<Output = ()> + Send,
+{
+    async fn rest_async(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let timeout = RequestTimeout::get(&request);
+        self.run(timeout, self.client.rest_async(request)).await
+    }
+
+    async fn rest_body_async(
+        &self,
+        request: Request<RequestBody>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let timeout = RequestTimeout::get(&request);
+        self.run(timeout, self.client.rest_body_async(request)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::*;
+    use crate::{AsyncQuery, Endpoint};
+
+    /// A server which answers `/fast` straight away, and never answers anything else.
+    struct Server;
+    impl RestClient for Server {
+        type Error = std::io::Error;
+
+        fn rest_endpoint(&self, target: &RequestTarget<'_>) -> Result<Uri, APIError<Self::Error>> {
+            Ok(crate::BaseUrl::parse("https://example.com")?.join(target)?)
+        }
+    }
+    impl AsyncClient for Server {
+        async fn rest_async(
+            &self,
+            request: Request<Vec<u8>>,
+        ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+            if request.uri().path() != "/fast" {
+                core::future::pending::<()>().await;
+            }
+            Ok(Response::new(Bytes::from_static(b"\"done\"")))
+        }
+    }
+
+    struct Fetch(&'static str, Option<Duration>);
+    impl Endpoint for Fetch {
+        fn path(&self) -> Cow<'static, str> {
+            self.0.into()
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.1
+        }
+    }
+
+    /// Poll `future` once.
+    fn poll<F: Future>(future: F) -> Poll<F::Output> {
+        let mut cx = core::task::Context::from_waker(core::task::Waker::noop());
+        pin!(future).poll(&mut cx)
+    }
+
+    fn fetch<S, F>(
+        client: &TimeoutClient<Server, S>,
+        endpoint: Fetch,
+    ) -> Poll<Result<String, APIError<std::io::Error>>>
+    where
+        S: Fn(Duration) -> F + Sync,
+        F: Future<Output = ()> + Send,
+    {
+        poll(AsyncQuery::<String, _>::query_async(&endpoint, client))
+    }
+
+    #[test]
+    fn times_out_with_the_request_timeout() {
+        // Every wait is over as soon as it starts
+        let client = TimeoutClient::new(Server, |_| async {});
+        let Poll::Ready(Err(error)) = fetch(&client, Fetch("slow", Some(Duration::from_secs(5))))
+        else {
+            panic!("expected the request to time out");
+        };
+        assert!(matches!(error.kind(), APIErrorKind::Timeout(x) if *x == Duration::from_secs(5)));
+        assert_eq!(
+            error.context().url.as_deref(),
+            Some("https://example.com/slow")
+        );
+
+        // Without a timeout, the request is left to run
+        assert!(fetch(&client, Fetch("slow", None)).is_pending());
+        let client = client.default_timeout(Duration::from_secs(1));
+        let Poll::Ready(Err(error)) = fetch(&client, Fetch("slow", None)) else {
+            panic!("expected the request to time out");
+        };
+        assert!(matches!(error.kind(), APIErrorKind::Timeout(x) if *x == Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn keeps_responses_within_the_timeout() {
+        let client = TimeoutClient::new(Server, |_| core::future::pending());
+        let result = fetch(&client, Fetch("fast", Some(Duration::from_secs(5))));
+        assert!(matches!(result, Poll::Ready(Ok(x)) if x == "done"));
+    }
+}
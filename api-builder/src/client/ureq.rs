@@ -0,0 +1,94 @@
+use core::time::Duration;
+
+use bytes::Bytes;
+use http::{Request, Response, header::CONTENT_LENGTH};
+use ureq::{Agent, SendBody};
+
+use crate::{APIError, APIErrorKind, RequestBody, RequestTimeout, RestClient};
+
+/// Maps ureq timeouts caused by a [`RequestTimeout`] to [`APIErrorKind::Timeout`].
+fn map_error<E>(error: ureq::Error, timeout: Option<Duration>) -> APIError<E> {
+    match (timeout, error) {
+        (Some(timeout), ureq::Error::Timeout(_)) => APIErrorKind::Timeout(timeout).into(),
+        (_, error) => error.into(),
+    }
+}
+
+/// A trait representing a client which includes a ureq agent.
+///
+/// Unlike [`ReqwestClient`](crate::ReqwestClient), this does not implement [`Client`](crate::Client) on its own.
+/// Forward [`Client::rest`](crate::Client::rest) to [`UreqClient::rest_ureq`], or use `#[derive(UreqClient)]` which does both.
+///
+/// Does not work on wasm.
+pub trait UreqClient: RestClient {
+    /// Get the ureq agent.
+    fn client(&self) -> &Agent;
+
+    /// Send a REST query with the ureq agent.
+    fn rest_ureq(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        self.run_ureq(request)
+    }
+
+    /// Send a REST query with a [`RequestBody`] using the ureq agent.
+    ///
+    /// Files and readers are streamed, asynchronous bodies fail with [`BodyError::AsyncOnly`](crate::BodyError::AsyncOnly).
+    fn rest_body_ureq(
+        &self,
+        request: Request<RequestBody>,
+    ) -> Result<Response<Bytes>, APIError<Self::Error>> {
+        let (mut parts, body) = request.into_parts();
+        if let Some(length) = body.len() {
+            parts
+                .headers
+                .entry(CONTENT_LENGTH)
+                .or_insert(length.into());
+        }
+
+        let body = match body {
+            RequestBody::Bytes(body) => return self.run_ureq(Request::from_parts(parts, body)),
+            RequestBody::File(path) => SendBody::from_owned_reader(
+                std::fs::File::open(path).map_err(crate::BodyError::from)?,
+            ),
+            RequestBody::Reader { reader, .. } => SendBody::from_owned_reader(reader),
+            #[cfg(feature = "stream")]
+            RequestBody::AsyncReader { .. } | RequestBody::Stream { .. } => {
+                Err(crate::BodyError::AsyncOnly)?
+            }
+        };
+        self.run_ureq(Request::from_parts(parts, body))
+    }
+
+    #[doc(hidden)]
+    fn run_ureq<S>(&self, request: Request<S>) -> Result<Response<Bytes>, APIError<Self::Error>>
+    where
+        S: ureq::AsSendBody,
+    {
+        // Non-success statuses are handled by the query, not ureq
+        let timeout = RequestTimeout::get(&request);
+        let mut config = self
+            .client()
+            .configure_request(request)
+            .http_status_as_error(false);
+        if timeout.is_some() {
+            config = config.timeout_global(timeout);
+        }
+
+        // Send the request
+        let response = self
+            .client()
+            .run(config.build())
+            .map_err(|e| map_error(e, timeout))?;
+
+        // Read the body and return the response
+        let (parts, mut body) = response.into_parts();
+        let body = body
+            .with_config()
+            .limit(u64::MAX)
+            .read_to_vec()
+            .map_err(|e| map_error(e, timeout))?;
+        Ok(Response::from_parts(parts, body.into()))
+    }
+}
@@ -2,11 +2,47 @@ use bytes::Bytes;
 use gloo_net::http::{Headers, RequestBuilder};
 use http::{Method, Request, Response, header::HeaderValue};
 use js_sys::Uint8Array;
-use web_sys::RequestCredentials;
+use web_sys::{
+    AbortSignal, ReferrerPolicy, RequestCache, RequestCredentials, RequestMode, RequestRedirect,
+};
 
 use crate::{APIError, AsyncClient, RestClient};
 
-pub trait WasmClient: RestClient {}
+/// A trait representing a client which uses `fetch` via `gloo-net`.
+///
+/// Each hook is called per request, so options can depend on the request.
+/// Hooks returning `None` leave the browser default in place.
+pub trait WasmClient: RestClient {
+    /// The credentials mode, defaults to [`RequestCredentials::Include`].
+    fn credentials(&self, _request: &Request<Vec<u8>>) -> RequestCredentials {
+        RequestCredentials::Include
+    }
+
+    /// The CORS mode.
+    fn mode(&self, _request: &Request<Vec<u8>>) -> Option<RequestMode> {
+        None
+    }
+
+    /// The cache mode.
+    fn cache(&self, _request: &Request<Vec<u8>>) -> Option<RequestCache> {
+        None
+    }
+
+    /// The redirect mode.
+    fn redirect(&self, _request: &Request<Vec<u8>>) -> Option<RequestRedirect> {
+        None
+    }
+
+    /// The referrer policy.
+    fn referrer_policy(&self, _request: &Request<Vec<u8>>) -> Option<ReferrerPolicy> {
+        None
+    }
+
+    /// A signal which aborts the request, e.g. from an `AbortController`.
+    fn abort_signal(&self, _request: &Request<Vec<u8>>) -> Option<AbortSignal> {
+        None
+    }
+}
 impl<C> AsyncClient for C
 where
     C: WasmClient + Sync,
@@ -16,16 +52,28 @@ where
         request: Request<Vec<u8>>,
     ) -> Result<Response<Bytes>, APIError<Self::Error>> {
         let headers = Headers::new();
-        request.headers().iter().for_each(|(key, value)| {
-            if let Ok(value) = value.to_str() {
-                headers.append(key.as_str(), value);
-            }
-        });
+        for (key, value) in request.headers() {
+            headers.append(key.as_str(), value.to_str()?);
+        }
 
-        let response = RequestBuilder::new(request.uri().to_string().as_str())
-            .credentials(RequestCredentials::Include)
+        let mut response = RequestBuilder::new(request.uri().to_string().as_str())
+            .credentials(self.credentials(&request))
             .method(request.method().clone())
             .headers(headers);
+        if let Some(mode) = self.mode(&request) {
+            response = response.mode(mode);
+        }
+        if let Some(cache) = self.cache(&request) {
+            response = response.cache(cache);
+        }
+        if let Some(redirect) = self.redirect(&request) {
+            response = response.redirect(redirect);
+        }
+        if let Some(referrer_policy) = self.referrer_policy(&request) {
+            response = response.referrer_policy(referrer_policy);
+        }
+        let signal = self.abort_signal(&request);
+        let response = response.abort_signal(signal.as_ref());
 
         let response = match request.method() {
             &Method::GET | &Method::HEAD => response.send().await?,
@@ -40,10 +88,7 @@ where
         let mut res = Response::builder().status(response.status());
 
         for (key, value) in response.headers().entries() {
-            let Ok(value) = HeaderValue::from_str(value.as_str()) else {
-                continue;
-            };
-
+            let value = HeaderValue::from_str(value.as_str())?;
             res = res.header(key.as_str(), value);
         }
 
@@ -0,0 +1,226 @@
+use core::ops::Deref;
+
+use bytes::Bytes;
+use http::{
+    HeaderValue, Response, StatusCode,
+    header::{ETAG, IF_MATCH, IF_NONE_MATCH},
+    request::Builder,
+};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    APIError, APIErrorKind, AsyncClient, AsyncQuery, Client, Endpoint, Query, async_queryer,
+    in_context, queryer,
+};
+
+/// A response along with the `ETag` the server returned for it.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct ETagged<T> {
+    /// The deserialized response.
+    pub value: T,
+    /// The new `ETag`, to be sent with the next conditional request.
+    pub etag: Option<String>,
+}
+impl<T> Deref for ETagged<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+/// Sends `If-Match` with a previously seen `ETag`, for optimistic concurrency.
+///
+/// A `412 Precondition Failed` is returned as [`APIErrorKind::PreconditionFailed`].
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct IfMatch<E>(pub E, pub String);
+impl<E> Deref for IfMatch<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Sends `If-None-Match` with a previously seen `ETag`, or `*` to only create a resource.
+///
+/// Returns `None` for a `304 Not Modified`, as the previously seen response is still current.
+/// A `412 Precondition Failed` is returned as [`APIErrorKind::PreconditionFailed`].
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct IfNoneMatch<E>(pub E, pub String);
+impl<E> Deref for IfNoneMatch<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Finalises a conditional request.
+fn finalise<E, T, C>(endpoint: &E, response: Response<Bytes>) -> Result<ETagged<T>, APIError<C>>
+where
+    E: Endpoint,
+    T: DeserializeOwned,
+{
+    if response.status() == StatusCode::PRECONDITION_FAILED {
+        Err(APIErrorKind::PreconditionFailed(response))?
+    } else if !response.status().is_success() && !endpoint.ignore_errors() {
+        Err(APIErrorKind::from_response(response))?
+    } else {
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .and_then(|x| x.to_str().ok())
+            .map(str::to_string);
+        Ok(ETagged {
+            value: endpoint.deserialize(response)?,
+            etag,
+        })
+    }
+}
+
+/// Finalises a request which may not have been modified.
+fn finalise_modified<E, T, C>(
+    endpoint: &E,
+    response: Response<Bytes>,
+) -> Result<Option<ETagged<T>>, APIError<C>>
+where
+    E: Endpoint,
+    T: DeserializeOwned,
+{
+    if response.status() == StatusCode::NOT_MODIFIED {
+        Ok(None)
+    } else {
+        finalise(endpoint, response).map(Some)
+    }
+}
+
+macro_rules! impl_conditional {
+    ($combinator:ident, $header:expr, $output:ty, $finalise:ident) => {
+        impl<E, T, C> Query<$output, C> for $combinator<E>
+        where
+            E: Endpoint,
+            T: DeserializeOwned,
+            C: Client,
+        {
+            queryer!("send");
+
+            fn request(&self, client: &C) -> Result<Builder, APIError<C::Error>> {
+                Ok(Query::<T, C>::request(&self.0, client)?
+                    .header($header, HeaderValue::from_str(&self.1)?))
+            }
+
+            fn finalise(&self, response: Response<Bytes>) -> Result<$output, APIError<C::Error>> {
+                $finalise(&self.0, response)
+            }
+
+            fn query(&self, client: &C) -> Result<$output, APIError<C::Error>> {
+                in_context!(sync Query<$output, C>, self, client)
+            }
+        }
+
+        impl<E, T, C> AsyncQuery<$output, C> for $combinator<E>
+        where
+            E: Endpoint + Sync,
+            T: DeserializeOwned,
+            C: AsyncClient + Sync,
+        {
+            async_queryer!("send");
+
+            async fn request_async(&self, client: &C) -> Result<Builder, APIError<C::Error>> {
+                Ok(AsyncQuery::<T, C>::request_async(&self.0, client)
+                    .await?
+                    .header($header, HeaderValue::from_str(&self.1)?))
+            }
+
+            async fn finalise_async(
+                &self,
+                response: Response<Bytes>,
+            ) -> Result<$output, APIError<C::Error>> {
+                $finalise(&self.0, response)
+            }
+
+            async fn query_async(&self, client: &C) -> Result<$output, APIError<C::Error>> {
+                in_context!(async AsyncQuery<$output, C>, self, client)
+            }
+        }
+    };
+}
+
+impl_conditional!(IfMatch, IF_MATCH, ETagged<T>, finalise);
+impl_conditional!(
+    IfNoneMatch,
+    IF_NONE_MATCH,
+    Option<ETagged<T>>,
+    finalise_modified
+);
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use http::{Request, Uri};
+
+    use super::*;
+    use crate::{BaseUrl, RequestTarget, RestClient};
+
+    /// A server holding a single resource with the `ETag` `"2"`.
+    struct Server;
+    impl RestClient for Server {
+        type Error = ();
+
+        fn rest_endpoint(&self, target: &RequestTarget<'_>) -> Result<Uri, APIError<()>> {
+            Ok(BaseUrl::parse("https://example.com")?.join(target)?)
+        }
+    }
+    impl Client for Server {
+        fn rest(&self, request: Request<Vec<u8>>) -> Result<Response<Bytes>, APIError<()>> {
+            let response = Response::builder().header(ETAG, "\"2\"");
+            let current = |name| request.headers().get(name).is_some_and(|x| x == "\"2\"");
+            let response =
+                if request.headers().contains_key(IF_NONE_MATCH) && current(IF_NONE_MATCH) {
+                    response.status(StatusCode::NOT_MODIFIED).body(Bytes::new())
+                } else if request.headers().contains_key(IF_MATCH) && !current(IF_MATCH) {
+                    response
+                        .status(StatusCode::PRECONDITION_FAILED)
+                        .body(Bytes::new())
+                } else {
+                    response.body(Bytes::from_static(b"\"pet\""))
+                };
+            Ok(response?)
+        }
+    }
+
+    struct GetPet;
+    impl Endpoint for GetPet {
+        fn path(&self) -> Cow<'static, str> {
+            "pet".into()
+        }
+    }
+
+    #[test]
+    fn if_none_match() {
+        let modified: Option<ETagged<String>> = IfNoneMatch(GetPet, "\"1\"".to_string())
+            .query(&Server)
+            .unwrap();
+        let modified = modified.unwrap();
+        assert_eq!(modified.value, "pet");
+        assert_eq!(modified.etag.as_deref(), Some("\"2\""));
+
+        let modified: Option<ETagged<String>> = IfNoneMatch(GetPet, "\"2\"".to_string())
+            .query(&Server)
+            .unwrap();
+        assert!(modified.is_none());
+    }
+
+    #[test]
+    fn if_match() {
+        let current: ETagged<String> = IfMatch(GetPet, "\"2\"".to_string()).query(&Server).unwrap();
+        assert_eq!(current.value, "pet");
+
+        let error =
+            Query::<ETagged<String>, _>::query(&IfMatch(GetPet, "\"1\"".to_string()), &Server)
+                .unwrap_err();
+        assert!(matches!(error.kind(), APIErrorKind::PreconditionFailed(_)));
+    }
+}
@@ -1,6 +1,8 @@
 #[cfg(feature = "prost")]
 import!(prost);
+#[cfg(feature = "stream")]
+import!(sse);
 
-import!(raw);
+import!(conditional, ndjson, raw, stream, typed);
 
 mod json;
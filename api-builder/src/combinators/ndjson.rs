@@ -0,0 +1,201 @@
+use core::ops::Deref;
+
+use bytes::Bytes;
+use http::Response;
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    APIError, APIErrorKind, AsyncClient, AsyncQuery, BodyError, Client, Endpoint, EndpointBody,
+    Query, RequestBody, async_queryer, in_context, queryer,
+};
+
+/// The content type for newline-delimited JSON.
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Deserializes a newline-delimited JSON (JSON Lines) response, one value per line.
+///
+/// Buffered queries return a [`Vec<T>`], use [`NdJson::stream_async`] to parse lines as they arrive.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct NdJson<E>(pub E);
+impl<E> Deref for NdJson<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl<E, T, C> Query<Vec<T>, C> for NdJson<E>
+where
+    E: Endpoint,
+    T: DeserializeOwned,
+    C: Client,
+{
+    queryer!("request");
+    queryer!("send");
+
+    fn query(&self, client: &C) -> Result<Vec<T>, APIError<C::Error>> {
+        in_context!(sync Query<Vec<T>, C>, self, client)
+    }
+
+    fn finalise(&self, response: Response<Bytes>) -> Result<Vec<T>, APIError<C::Error>> {
+        if !response.status().is_success() && !self.0.ignore_errors() {
+            Err(APIErrorKind::from_response(response))?
+        } else {
+            Ok(from_ndjson(response.body())?)
+        }
+    }
+}
+
+impl<E, T, C> AsyncQuery<Vec<T>, C> for NdJson<E>
+where
+    E: Endpoint + Sync,
+    T: DeserializeOwned,
+    C: AsyncClient + Sync,
+{
+    async_queryer!("request");
+    async_queryer!("send");
+
+    async fn query_async(&self, client: &C) -> Result<Vec<T>, APIError<C::Error>> {
+        in_context!(async AsyncQuery<Vec<T>, C>, self, client)
+    }
+
+    async fn finalise_async(
+        &self,
+        response: Response<Bytes>,
+    ) -> Result<Vec<T>, APIError<C::Error>> {
+        if !response.status().is_success() && !self.0.ignore_errors() {
+            Err(APIErrorKind::from_response(response))?
+        } else {
+            Ok(from_ndjson(response.body())?)
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<E> NdJson<E>
+where
+    E: Endpoint + Sync,
+{
+    /// Sends the request and returns a stream of values, parsed line by line as chunks arrive.
+    pub async fn stream_async<'a, T, C>(
+        &'a self,
+        client: &'a C,
+    ) -> Result<
+        impl futures_core::Stream<Item = Result<T, APIError<C::Error>>> + 'a,
+        APIError<C::Error>,
+    >
+    where
+        T: DeserializeOwned + 'a,
+        C: crate::AsyncStreamingClient + Sync,
+        C::Error: core::error::Error + Sync + Send + 'static,
+    {
+        use futures_util::StreamExt;
+
+        let response =
+            crate::AsyncStreamQuery::<C>::query_async(&crate::Stream(&self.0), client).await?;
+        let state = (
+            response.into_body(),
+            Vec::new(),
+            std::collections::VecDeque::new(),
+            false,
+        );
+
+        Ok(futures_util::stream::unfold(
+            state,
+            |(mut body, mut buf, mut pending, mut done)| async move {
+                loop {
+                    if let Some(item) = pending.pop_front() {
+                        return Some((item, (body, buf, pending, done)));
+                    }
+                    if done {
+                        return None;
+                    }
+
+                    match body.next().await {
+                        Some(Ok(chunk)) => {
+                            buf.extend_from_slice(&chunk);
+
+                            // Parse every complete line
+                            if let Some(end) = buf.iter().rposition(|&b| b == b'\n') {
+                                let rest = buf.split_off(end + 1);
+                                pending.extend(ndjson_lines(&buf).map(|x| x.map_err(Into::into)));
+                                buf = rest;
+                            }
+                        }
+                        Some(Err(e)) => {
+                            done = true;
+                            pending.push_back(Err(e));
+                        }
+                        None => {
+                            // The last line may not end in a newline
+                            done = true;
+                            pending.extend(ndjson_lines(&buf).map(|x| x.map_err(Into::into)));
+                            buf.clear();
+                        }
+                    }
+                }
+            },
+        ))
+    }
+}
+
+/// Parses each non-blank line as a JSON value.
+fn ndjson_lines<T: DeserializeOwned>(
+    bytes: &[u8],
+) -> impl Iterator<Item = Result<T, BodyError>> + '_ {
+    bytes
+        .split(|&b| b == b'\n')
+        .filter(|line| !line.trim_ascii().is_empty())
+        .map(|line| Ok(serde_json::from_slice(line)?))
+}
+
+/// Deserializes newline-delimited JSON, skipping blank lines.
+pub fn from_ndjson<T: DeserializeOwned>(bytes: &[u8]) -> Result<Vec<T>, BodyError> {
+    ndjson_lines(bytes).collect()
+}
+
+/// Serializes each value as a single line of JSON.
+pub fn to_ndjson<I>(values: I) -> Result<Vec<u8>, BodyError>
+where
+    I: IntoIterator,
+    I::Item: Serialize,
+{
+    let mut buf = Vec::new();
+    for value in values {
+        serde_json::to_writer(&mut buf, &value)?;
+        buf.push(b'\n');
+    }
+    Ok(buf)
+}
+
+/// Builds a newline-delimited JSON body, for use in [`Endpoint::request_body`].
+pub fn ndjson_body<I>(values: I) -> Result<EndpointBody, BodyError>
+where
+    I: IntoIterator,
+    I::Item: Serialize,
+{
+    Ok((
+        NDJSON_CONTENT_TYPE.into(),
+        RequestBody::Bytes(to_ndjson(values)?),
+    ))
+}
+
+/// Builds a chunked newline-delimited JSON body from a stream, for use in [`Endpoint::request_body`].
+///
+/// Each value is serialized as it is sent.
+#[cfg(feature = "stream")]
+#[cfg(not(target_arch = "wasm32"))]
+pub fn ndjson_stream_body<S>(values: S) -> EndpointBody
+where
+    S: futures_core::Stream + Send + 'static,
+    S::Item: Serialize,
+{
+    use futures_util::StreamExt;
+
+    let stream = values.map(|value| {
+        let mut line = serde_json::to_vec(&value).map_err(std::io::Error::other)?;
+        line.push(b'\n');
+        Ok(Bytes::from(line))
+    });
+    (NDJSON_CONTENT_TYPE.into(), RequestBody::stream(stream))
+}
@@ -30,7 +30,7 @@ where
 
     fn finalise(&self, response: Response<Bytes>) -> Result<T, APIError<C::Error>> {
         if !response.status().is_success() && !self.0.ignore_errors() {
-            Err(APIErrorKind::Response(response))?
+            Err(APIErrorKind::from_response(response))?
         } else {
             // Deserialize the response
             Ok(T::decode(response.body().clone())
@@ -51,7 +51,7 @@ where
 
     async fn finalise_async(&self, response: Response<Bytes>) -> Result<T, APIError<C::Error>> {
         if !response.status().is_success() && !self.0.ignore_errors() {
-            Err(APIErrorKind::Response(response))?
+            Err(APIErrorKind::from_response(response))?
         } else {
             // Deserialize the response
             Ok(T::decode(response.into_body())
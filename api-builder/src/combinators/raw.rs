@@ -5,7 +5,7 @@ use http::Response;
 
 use crate::{
     APIError, APIErrorKind, AsyncClient, AsyncQuery, Client, Endpoint, Query, async_queryer,
-    queryer,
+    in_context, queryer,
 };
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
@@ -26,19 +26,12 @@ where
     queryer!("send");
 
     fn query(&self, client: &C) -> Result<Response<Bytes>, APIError<C::Error>> {
-        Query::<Response<Bytes>, C>::finalise(
-            self,
-            Query::<Response<Bytes>, C>::send(
-                self,
-                client,
-                Query::<Response<Bytes>, C>::request(self, client)?,
-            )?,
-        )
+        in_context!(sync Query<Response<Bytes>, C>, self, client)
     }
 
     fn finalise(&self, response: Response<Bytes>) -> Result<Response<Bytes>, APIError<C::Error>> {
         if !response.status().is_success() && !self.0.ignore_errors() {
-            Err(APIErrorKind::Response(response))?
+            Err(APIErrorKind::from_response(response))?
         } else {
             // Deserialize the response
             Ok(response)
@@ -55,16 +48,7 @@ where
     async_queryer!("send");
 
     async fn query_async(&self, client: &C) -> Result<Response<Bytes>, APIError<C::Error>> {
-        AsyncQuery::<Response<Bytes>, C>::finalise_async(
-            self,
-            AsyncQuery::<Response<Bytes>, C>::send_async(
-                self,
-                client,
-                AsyncQuery::<Response<Bytes>, C>::request_async(self, client).await?,
-            )
-            .await?,
-        )
-        .await
+        in_context!(async AsyncQuery<Response<Bytes>, C>, self, client)
     }
 
     async fn finalise_async(
@@ -72,7 +56,7 @@ where
         response: Response<Bytes>,
     ) -> Result<Response<Bytes>, APIError<C::Error>> {
         if !response.status().is_success() && !self.0.ignore_errors() {
-            Err(APIErrorKind::Response(response))?
+            Err(APIErrorKind::from_response(response))?
         } else {
             // Deserialize the response
             Ok(response)
@@ -0,0 +1,571 @@
+use core::{future::Future, ops::Deref, time::Duration};
+use std::collections::VecDeque;
+
+use bytes::Bytes;
+use futures_core::Stream as FuturesStream;
+use futures_util::StreamExt;
+use http::{HeaderName, HeaderValue, Response, header::ACCEPT, request::Builder};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    APIError, APIErrorKind, AsyncStreamQuery, AsyncStreamingClient, BodyError, Endpoint,
+    QueryContext, async_queryer, in_context,
+};
+
+/// The `Last-Event-ID` header, sent when reconnecting.
+pub const LAST_EVENT_ID: HeaderName = HeaderName::from_static("last-event-id");
+
+/// The default reconnection delay, used until the server sends `retry`.
+pub const DEFAULT_RETRY: Duration = Duration::from_secs(3);
+
+/// Consumes a `text/event-stream` endpoint as a stream of [`SseEvent`]s.
+///
+/// Reconnects automatically with `Last-Event-ID` when the connection drops, see [`Sse::events_with`].
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Sse<E>(pub E);
+impl<E> Deref for Sse<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl<E, C> AsyncStreamQuery<C> for Sse<E>
+where
+    E: Endpoint + Sync,
+    C: AsyncStreamingClient + Sync,
+{
+    async_queryer!("request");
+
+    async fn send_async(
+        &self,
+        client: &C,
+        request: Builder,
+    ) -> Result<Response<C::Stream>, APIError<C::Error>> {
+        let request = request.header(ACCEPT, "text/event-stream");
+        if let Some((mime, body)) = self.request_body()? {
+            let body = body.into_bytes_async().await?;
+            client
+                .rest_stream_async(
+                    request
+                        .header(
+                            http::header::CONTENT_TYPE,
+                            HeaderValue::from_str(&mime)?,
+                        )
+                        .body(body)?,
+                )
+                .await
+        } else {
+            client.rest_stream_async(request.body(Vec::new())?).await
+        }
+    }
+
+    async fn finalise_async(
+        &self,
+        response: Response<C::Stream>,
+    ) -> Result<Response<C::Stream>, APIError<C::Error>> {
+        if !response.status().is_success() && !self.0.ignore_errors() {
+            // Buffer the body so it can be included in the error
+            let (parts, mut body) = response.into_parts();
+            let mut buf = Vec::new();
+            while let Some(chunk) = body.next().await {
+                buf.extend_from_slice(&chunk?);
+            }
+            Err(APIErrorKind::from_response(Response::from_parts(
+                parts,
+                buf.into(),
+            )))?
+        } else {
+            Ok(response)
+        }
+    }
+
+    async fn query_async(&self, client: &C) -> Result<Response<C::Stream>, APIError<C::Error>>
+    where
+        C::Error: core::error::Error + Sync + Send + 'static,
+    {
+        in_context!(async AsyncStreamQuery<C>, self, client)
+    }
+}
+impl<E> Sse<E>
+where
+    E: Endpoint + Sync,
+{
+    /// Connects and returns the stream of events, using `sleep` to wait between reconnections.
+    ///
+    /// The connection is re-established whenever the body ends or fails mid-stream, sending the last seen event ID.
+    /// Errors are yielded before reconnecting, waiting [`SseParser::retry`] between each attempt.
+    /// The stream ends when the server responds with `204 No Content`, or after an error which isn't retryable, see [`APIError::is_retryable`].
+    pub fn events_with<'a, C, S, F>(
+        &'a self,
+        client: &'a C,
+        sleep: S,
+    ) -> impl FuturesStream<Item = Result<SseEvent, APIError<C::Error>>> + 'a
+    where
+        C: AsyncStreamingClient + Sync,
+        C::Error: core::error::Error + Sync + Send + 'static,
+        S: Fn(Duration) -> F + 'a,
+        F: Future<Output = ()> + 'a,
+    {
+        let state = EventsState::<C::Stream, S> {
+            sleep,
+            body: None,
+            parser: SseParser::default(),
+            reconnecting: false,
+            done: false,
+        };
+
+        futures_util::stream::unfold(state, move |mut state| {
+            async move {
+                loop {
+                    if let Some(event) = state.parser.next_event() {
+                        return Some((Ok(event), state));
+                    }
+                    if state.done {
+                        return None;
+                    }
+
+                    // Read the next chunk
+                    if let Some(body) = &mut state.body {
+                        match body.next().await {
+                            Some(Ok(chunk)) => state.parser.push(&chunk),
+                            Some(Err(e)) => {
+                                state.body = None;
+                                state.reconnecting = true;
+                                return Some((Err(e), state));
+                            }
+                            None => {
+                                state.body = None;
+                                state.reconnecting = true;
+                            }
+                        }
+                        continue;
+                    }
+
+                    // (Re)connect
+                    if state.reconnecting {
+                        (state.sleep)(state.parser.retry()).await;
+                    }
+                    state.parser.reset();
+                    match self.connect(client, state.parser.last_event_id()).await {
+                        Ok(Some(body)) => state.body = Some(body),
+                        Ok(None) => state.done = true,
+                        Err(e) => {
+                            state.reconnecting = true;
+                            state.done = !e.is_retryable();
+                            return Some((Err(e), state));
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Connects and returns the stream of events, with each event's data deserialized as JSON.
+    ///
+    /// See [`Sse::events_with`].
+    pub fn json_events_with<'a, T, C, S, F>(
+        &'a self,
+        client: &'a C,
+        sleep: S,
+    ) -> impl FuturesStream<Item = Result<T, APIError<C::Error>>> + 'a
+    where
+        T: DeserializeOwned + 'a,
+        C: AsyncStreamingClient + Sync,
+        C::Error: core::error::Error + Sync + Send + 'static,
+        S: Fn(Duration) -> F + 'a,
+        F: Future<Output = ()> + 'a,
+    {
+        self.events_with(client, sleep)
+            .map(|event| Ok(event?.json()?))
+    }
+
+    /// Connects and returns the stream of events, using `tokio` to wait between reconnections.
+    ///
+    /// See [`Sse::events_with`].
+    #[cfg(feature = "tokio")]
+    pub fn events<'a, C>(
+        &'a self,
+        client: &'a C,
+    ) -> impl FuturesStream<Item = Result<SseEvent, APIError<C::Error>>> + 'a
+    where
+        C: AsyncStreamingClient + Sync,
+        C::Error: core::error::Error + Sync + Send + 'static,
+    {
+        self.events_with(client, tokio::time::sleep)
+    }
+
+    /// Connects and returns the stream of events, with each event's data deserialized as JSON.
+    ///
+    /// See [`Sse::events_with`].
+    #[cfg(feature = "tokio")]
+    pub fn json_events<'a, T, C>(
+        &'a self,
+        client: &'a C,
+    ) -> impl FuturesStream<Item = Result<T, APIError<C::Error>>> + 'a
+    where
+        T: DeserializeOwned + 'a,
+        C: AsyncStreamingClient + Sync,
+        C::Error: core::error::Error + Sync + Send + 'static,
+    {
+        self.json_events_with(client, tokio::time::sleep)
+    }
+
+    /// Sends the request, returning [`None`] if the server asked not to reconnect.
+    async fn connect<C>(
+        &self,
+        client: &C,
+        last_event_id: Option<&str>,
+    ) -> Result<Option<C::Stream>, APIError<C::Error>>
+    where
+        C: AsyncStreamingClient + Sync,
+        C::Error: core::error::Error + Sync + Send + 'static,
+    {
+        // Like `in_context!`, with `Last-Event-ID` added to the request
+        let mut context = QueryContext::new::<Self>();
+        let response = async {
+            let mut request = AsyncStreamQuery::<C>::request_async(self, client).await?;
+            if let Some(id) = last_event_id {
+                request = request.header(LAST_EVENT_ID, HeaderValue::from_str(id)?);
+            }
+            context.request(&request);
+
+            AsyncStreamQuery::<C>::finalise_async(
+                self,
+                AsyncStreamQuery::<C>::send_async(self, client, request).await?,
+            )
+            .await
+        }
+        .await
+        .map_err(|e| context.attach(e))?;
+        if response.status() == http::StatusCode::NO_CONTENT {
+            return Ok(None);
+        }
+
+        Ok(Some(response.into_body()))
+    }
+}
+
+struct EventsState<B, S> {
+    sleep: S,
+    body: Option<B>,
+    parser: SseParser,
+    reconnecting: bool,
+    done: bool,
+}
+
+/// A single server-sent event.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct SseEvent {
+    /// The event type, [`None`] means `message`.
+    pub event: Option<String>,
+    /// The event data, with multiple `data` lines joined by `\n`.
+    pub data: String,
+    /// The last event ID seen on the stream.
+    pub id: Option<String>,
+    /// The reconnection time sent alongside this event.
+    pub retry: Option<Duration>,
+}
+impl SseEvent {
+    /// Deserialize the event data as JSON.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, BodyError> {
+        Ok(serde_json::from_str(&self.data)?)
+    }
+}
+
+/// An incremental `text/event-stream` parser.
+#[derive(Clone, Debug, Default)]
+pub struct SseParser {
+    buf: Vec<u8>,
+    skipped_cr: bool,
+    started: bool,
+    event: Option<String>,
+    data: String,
+    has_data: bool,
+    last_event_id: Option<String>,
+    retry: Option<Duration>,
+    pending_retry: Option<Duration>,
+    events: VecDeque<SseEvent>,
+}
+impl SseParser {
+    /// Feed a chunk of the body into the parser.
+    pub fn push(&mut self, chunk: &[u8]) {
+        let mut chunk = chunk;
+
+        // A CRLF may have been split across chunks
+        if self.skipped_cr {
+            self.skipped_cr = false;
+            if let Some(rest) = chunk.strip_prefix(b"\n") {
+                chunk = rest;
+            }
+        }
+
+        while let Some(end) = chunk.iter().position(|&b| b == b'\n' || b == b'\r') {
+            self.buf.extend_from_slice(&chunk[..end]);
+            let line = core::mem::take(&mut self.buf);
+            self.line(&line);
+
+            if chunk[end] == b'\r' {
+                match chunk.get(end + 1) {
+                    Some(b'\n') => chunk = &chunk[end + 2..],
+                    Some(_) => chunk = &chunk[end + 1..],
+                    None => {
+                        self.skipped_cr = true;
+                        chunk = &[];
+                    }
+                }
+            } else {
+                chunk = &chunk[end + 1..];
+            }
+        }
+        self.buf.extend_from_slice(chunk);
+    }
+
+    /// Take the next parsed event.
+    pub fn next_event(&mut self) -> Option<SseEvent> {
+        self.events.pop_front()
+    }
+
+    /// The last event ID seen, to be sent as `Last-Event-ID` when reconnecting.
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.last_event_id.as_deref()
+    }
+
+    /// The reconnection time, from the latest `retry` field or [`DEFAULT_RETRY`].
+    pub fn retry(&self) -> Duration {
+        self.retry.unwrap_or(DEFAULT_RETRY)
+    }
+
+    /// Discard any partially received event, keeping the last event ID and reconnection time.
+    pub fn reset(&mut self) {
+        self.buf.clear();
+        self.skipped_cr = false;
+        self.started = false;
+        self.event = None;
+        self.data.clear();
+        self.has_data = false;
+    }
+
+    fn line(&mut self, line: &[u8]) {
+        let mut line = String::from_utf8_lossy(line);
+        if !self.started {
+            self.started = true;
+            if let Some(rest) = line.strip_prefix('\u{feff}') {
+                line = rest.to_string().into();
+            }
+        }
+
+        // Dispatch the event
+        if line.is_empty() {
+            self.dispatch();
+            return;
+        }
+
+        // Comments
+        if line.starts_with(':') {
+            return;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_ref(), ""),
+        };
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => {
+                if self.has_data {
+                    self.data.push('\n');
+                }
+                self.data.push_str(value);
+                self.has_data = true;
+            }
+            "id" if !value.contains('\0') => {
+                self.last_event_id = (!value.is_empty()).then(|| value.to_string());
+            }
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                if let Ok(ms) = value.parse() {
+                    let retry = Duration::from_millis(ms);
+                    self.retry = Some(retry);
+                    self.pending_retry = Some(retry);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn dispatch(&mut self) {
+        let event = self.event.take();
+        if !core::mem::take(&mut self.has_data) {
+            return;
+        }
+
+        self.events.push_back(SseEvent {
+            event,
+            data: core::mem::take(&mut self.data),
+            id: self.last_event_id.clone(),
+            retry: self.pending_retry.take(),
+        });
+    }
+}
+
+/// Parses an entire buffered `text/event-stream` body.
+pub fn parse_sse(body: &Bytes) -> Vec<SseEvent> {
+    let mut parser = SseParser::default();
+    parser.push(body);
+    parser.push(b"\n");
+    parser.events.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(chunks: &[&[u8]]) -> Vec<SseEvent> {
+        let mut parser = SseParser::default();
+        for chunk in chunks {
+            parser.push(chunk);
+        }
+        core::iter::from_fn(|| parser.next_event()).collect()
+    }
+
+    #[test]
+    fn joins_data_lines() {
+        let events = parse(&[b"data: first\ndata:second\ndata\n\n: comment\n\n"]);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "first\nsecond\n");
+        assert_eq!(events[0].event, None);
+    }
+
+    #[test]
+    fn keeps_id_and_retry() {
+        let mut parser = SseParser::default();
+        parser.push(b"event: update\nid: 1\nretry: 500\ndata: a\n\ndata: b\n\nid\ndata: c\n\n");
+        let events: Vec<_> = core::iter::from_fn(|| parser.next_event()).collect();
+
+        assert_eq!(events[0].event.as_deref(), Some("update"));
+        assert_eq!(events[0].id.as_deref(), Some("1"));
+        assert_eq!(events[0].retry, Some(Duration::from_millis(500)));
+        // The ID is kept, the retry is only sent with the event it came with
+        assert_eq!(events[1].event, None);
+        assert_eq!(events[1].id.as_deref(), Some("1"));
+        assert_eq!(events[1].retry, None);
+        // An empty ID resets it
+        assert_eq!(events[2].id, None);
+        assert_eq!(parser.retry(), Duration::from_millis(500));
+
+        parser.push(b"retry: soon\nid: a\0b\ndata: d\n\n");
+        assert_eq!(parser.retry(), Duration::from_millis(500));
+        assert_eq!(parser.next_event().unwrap().id, None);
+    }
+
+    #[test]
+    fn splits_lines_on_crlf_and_cr() {
+        let events = parse(&[b"data: a\r", b"\ndata: b\r\r\ndata: c\n", b"\r\n"]);
+        let data: Vec<_> = events.iter().map(|x| x.data.as_str()).collect();
+        assert_eq!(data, ["a\nb", "c"]);
+    }
+
+    #[test]
+    fn skips_byte_order_mark() {
+        let events = parse(&[b"\xEF\xBB", b"\xBFdata: a\n\n\xEF\xBB\xBFdata: b\n\n"]);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "a");
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn reconnects_after_errors() {
+        use std::sync::Mutex;
+
+        use http::{Request, Uri};
+
+        use crate::{BaseUrl, BytesStream, RequestTarget, RestClient};
+
+        type Error = std::io::Error;
+
+        /// How a connection to the server goes.
+        enum Connection {
+            Refused,
+            Body(&'static str),
+            Reset(&'static str),
+        }
+
+        /// Serves each connection in turn, then `204 No Content`.
+        struct Server(Mutex<VecDeque<Connection>>);
+        impl RestClient for Server {
+            type Error = Error;
+
+            fn rest_endpoint(&self, target: &RequestTarget<'_>) -> Result<Uri, APIError<Error>> {
+                Ok(BaseUrl::parse("https://example.com")?.join(target)?)
+            }
+        }
+        impl AsyncStreamingClient for Server {
+            type Stream = BytesStream<Error>;
+
+            async fn rest_stream_async(
+                &self,
+                _: Request<Vec<u8>>,
+            ) -> Result<Response<Self::Stream>, APIError<Error>> {
+                let reset = || APIError::from(anyhow::anyhow!("reset"));
+                let chunks = match self.0.lock().unwrap().pop_front() {
+                    Some(Connection::Refused) => {
+                        Err(APIErrorKind::Timeout(Duration::from_secs(1)))?
+                    }
+                    Some(Connection::Body(body)) => vec![Ok(Bytes::from(body))],
+                    Some(Connection::Reset(body)) => vec![Ok(Bytes::from(body)), Err(reset())],
+                    None => {
+                        let stream: Self::Stream = Box::pin(futures_util::stream::empty());
+                        return Ok(Response::builder().status(204).body(stream)?);
+                    }
+                };
+                let stream: Self::Stream = Box::pin(futures_util::stream::iter(chunks));
+                Ok(Response::new(stream))
+            }
+        }
+
+        struct Events;
+        impl Endpoint for Events {
+            fn path(&self) -> std::borrow::Cow<'static, str> {
+                "events".into()
+            }
+        }
+
+        let server = Server(Mutex::new(VecDeque::from([
+            Connection::Reset("retry: 10\ndata: a\n\ndata: lost"),
+            Connection::Refused,
+            Connection::Body("data: b\n\n"),
+        ])));
+        let sleeps = Mutex::new(Vec::new());
+        let sse = Sse(Events);
+        let events = sse.events_with(&server, |x| {
+            sleeps.lock().unwrap().push(x);
+            async {}
+        });
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let events: Vec<_> = runtime.block_on(events.collect());
+        // Failed connections say what was being called
+        let refused = events[2].as_ref().unwrap_err().context();
+        assert_eq!(refused.method, Some(http::Method::GET));
+        assert_eq!(refused.url.as_deref(), Some("https://example.com/events"));
+        assert_eq!(refused.endpoint_name().as_deref(), Some("Sse<Events>"));
+        assert!(refused.elapsed.is_some());
+
+        let events: Vec<_> = events
+            .into_iter()
+            .map(|x| x.map(|x| x.data).map_err(|e| e.is_timeout()))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                Ok("a".to_string()),
+                Err(false),
+                Err(true),
+                Ok("b".to_string())
+            ]
+        );
+        assert_eq!(*sleeps.lock().unwrap(), [Duration::from_millis(10); 3]);
+    }
+}
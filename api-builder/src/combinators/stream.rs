@@ -0,0 +1,277 @@
+use core::ops::Deref;
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::Path,
+};
+
+use http::{Response, header::CONTENT_LENGTH, request::Builder};
+
+use crate::{
+    APIError, APIErrorKind, BodyError, Endpoint, StreamQuery, StreamingClient, in_context, queryer,
+};
+
+/// The most of an error response's body which is buffered into the error.
+///
+/// Enough for any problem details, while a misbehaving server can't make the error grow without bound.
+const ERROR_BODY_LIMIT: usize = 64 * 1024;
+
+/// Streams the response body instead of buffering it.
+///
+/// Useful for large downloads, see [`download`] and [`download_to_file`].
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Stream<E>(pub E);
+impl<E> Deref for Stream<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl<E, C> StreamQuery<C> for Stream<E>
+where
+    E: Endpoint,
+    C: StreamingClient,
+{
+    queryer!("request");
+
+    fn send(&self, client: &C, request: Builder) -> Result<Response<C::Body>, APIError<C::Error>> {
+        if let Some((mime, body)) = self.request_body()? {
+            client.rest_stream(
+                request
+                    .header(http::header::CONTENT_TYPE, mime.as_ref())
+                    .body(body.into_bytes()?)?,
+            )
+        } else {
+            client.rest_stream(request.body(Vec::new())?)
+        }
+    }
+
+    fn finalise(
+        &self,
+        response: Response<C::Body>,
+    ) -> Result<Response<C::Body>, APIError<C::Error>> {
+        if !response.status().is_success() && !self.0.ignore_errors() {
+            // Buffer the body so it can be included in the error
+            let (parts, body) = response.into_parts();
+            let mut buf = Vec::new();
+            body.take(ERROR_BODY_LIMIT as u64)
+                .read_to_end(&mut buf)
+                .map_err(BodyError::from)?;
+            Err(APIErrorKind::from_response(Response::from_parts(
+                parts,
+                buf.into(),
+            )))?
+        } else {
+            Ok(response)
+        }
+    }
+
+    fn query(&self, client: &C) -> Result<Response<C::Body>, APIError<C::Error>> {
+        in_context!(sync StreamQuery<C>, self, client)
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<E, C> crate::AsyncStreamQuery<C> for Stream<E>
+where
+    E: Endpoint + Sync,
+    C: crate::AsyncStreamingClient + Sync,
+{
+    crate::async_queryer!("request");
+
+    async fn send_async(
+        &self,
+        client: &C,
+        request: Builder,
+    ) -> Result<Response<C::Stream>, APIError<C::Error>> {
+        if let Some((mime, body)) = self.request_body()? {
+            let body = body.into_bytes_async().await?;
+            client
+                .rest_stream_async(
+                    request
+                        .header(
+                            http::header::CONTENT_TYPE,
+                            http::header::HeaderValue::from_str(&mime)?,
+                        )
+                        .body(body)?,
+                )
+                .await
+        } else {
+            client.rest_stream_async(request.body(Vec::new())?).await
+        }
+    }
+
+    async fn finalise_async(
+        &self,
+        response: Response<C::Stream>,
+    ) -> Result<Response<C::Stream>, APIError<C::Error>> {
+        use futures_util::StreamExt;
+
+        if !response.status().is_success() && !self.0.ignore_errors() {
+            // Buffer the body so it can be included in the error
+            let (parts, mut body) = response.into_parts();
+            let mut buf = Vec::new();
+            while buf.len() < ERROR_BODY_LIMIT
+                && let Some(chunk) = body.next().await
+            {
+                buf.extend_from_slice(&chunk?);
+            }
+            buf.truncate(ERROR_BODY_LIMIT);
+            Err(APIErrorKind::from_response(Response::from_parts(
+                parts,
+                buf.into(),
+            )))?
+        } else {
+            Ok(response)
+        }
+    }
+
+    async fn query_async(&self, client: &C) -> Result<Response<C::Stream>, APIError<C::Error>>
+    where
+        C::Error: core::error::Error + Sync + Send + 'static,
+    {
+        use crate::AsyncStreamQuery;
+
+        in_context!(async AsyncStreamQuery<C>, self, client)
+    }
+}
+
+/// The progress of a download.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Progress {
+    /// The number of bytes written so far.
+    pub downloaded: u64,
+    /// The total number of bytes, if the server sent `Content-Length`.
+    pub total: Option<u64>,
+}
+impl Progress {
+    fn new<T>(response: &Response<T>) -> Self {
+        Self {
+            downloaded: 0,
+            total: response
+                .headers()
+                .get(CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok()),
+        }
+    }
+}
+
+/// Writes a streamed response body into `writer`, calling `progress` after each chunk.
+///
+/// Returns the number of bytes written.
+pub fn download<R, W, E>(
+    response: Response<R>,
+    writer: &mut W,
+    mut progress: impl FnMut(Progress),
+) -> Result<u64, APIError<E>>
+where
+    R: Read,
+    W: Write,
+{
+    let mut state = Progress::new(&response);
+    let mut body = response.into_body();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let read = body.read(&mut buf).map_err(BodyError::from)?;
+        if read == 0 {
+            break;
+        }
+
+        writer.write_all(&buf[..read]).map_err(BodyError::from)?;
+        state.downloaded += read as u64;
+        progress(state);
+    }
+    writer.flush().map_err(BodyError::from)?;
+
+    Ok(state.downloaded)
+}
+
+/// Writes a streamed response body into the file at `path`, calling `progress` after each chunk.
+///
+/// The file is created, or truncated if it already exists. Returns the number of bytes written.
+pub fn download_to_file<R, E>(
+    response: Response<R>,
+    path: impl AsRef<Path>,
+    progress: impl FnMut(Progress),
+) -> Result<u64, APIError<E>>
+where
+    R: Read,
+{
+    let mut file = File::create(path).map_err(BodyError::from)?;
+    download(response, &mut file, progress)
+}
+
+/// Writes a streamed response body into `writer`, calling `progress` after each chunk.
+///
+/// Returns the number of bytes written.
+#[cfg(feature = "stream")]
+pub async fn download_async<S, W, E>(
+    response: Response<S>,
+    writer: &mut W,
+    mut progress: impl FnMut(Progress),
+) -> Result<u64, APIError<E>>
+where
+    S: futures_core::Stream<Item = Result<bytes::Bytes, APIError<E>>> + Unpin,
+    W: futures_util::io::AsyncWrite + Unpin,
+{
+    use futures_util::{StreamExt, io::AsyncWriteExt};
+
+    let mut state = Progress::new(&response);
+    let mut body = response.into_body();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk?;
+        writer.write_all(&chunk).await.map_err(BodyError::from)?;
+        state.downloaded += chunk.len() as u64;
+        progress(state);
+    }
+    writer.flush().await.map_err(BodyError::from)?;
+
+    Ok(state.downloaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use http::{Request, StatusCode, Uri};
+
+    use super::*;
+    use crate::{BaseUrl, RequestTarget, RestClient};
+
+    /// A server which fails with an endless body.
+    struct Server;
+    impl RestClient for Server {
+        type Error = ();
+
+        fn rest_endpoint(&self, target: &RequestTarget<'_>) -> Result<Uri, APIError<()>> {
+            Ok(BaseUrl::parse("https://example.com")?.join(target)?)
+        }
+    }
+    impl StreamingClient for Server {
+        type Body = std::io::Repeat;
+
+        fn rest_stream(&self, _: Request<Vec<u8>>) -> Result<Response<Self::Body>, APIError<()>> {
+            Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(std::io::repeat(b'a'))?)
+        }
+    }
+
+    struct Download;
+    impl Endpoint for Download {
+        fn path(&self) -> Cow<'static, str> {
+            "download".into()
+        }
+    }
+
+    #[test]
+    fn limits_error_bodies() {
+        let error = Stream(Download).query(&Server).unwrap_err();
+        let APIErrorKind::Response(response) = error.kind() else {
+            panic!("expected a response error");
+        };
+        assert_eq!(response.body().len(), ERROR_BODY_LIMIT);
+    }
+}
@@ -0,0 +1,56 @@
+use core::ops::Deref;
+
+use bytes::Bytes;
+use http::Response;
+
+use crate::{
+    APIError, APIErrorKind, AsyncClient, AsyncQuery, Client, Query, TypedEndpoint, async_queryer,
+    queryer,
+};
+
+/// Decodes the response with [`TypedEndpoint::decode`], so the response type is inferred.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Typed<E>(pub E);
+impl<E> Deref for Typed<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl<E, T, C> Query<T, C> for Typed<E>
+where
+    E: TypedEndpoint<Response = T>,
+    C: Client,
+{
+    queryer!("request");
+    queryer!("send");
+    queryer!("query");
+
+    fn finalise(&self, response: Response<Bytes>) -> Result<T, APIError<C::Error>> {
+        if !response.status().is_success() && !self.0.ignore_errors() {
+            Err(APIErrorKind::from_response(response))?
+        } else {
+            Ok(self.0.decode(response)?)
+        }
+    }
+}
+
+impl<E, T, C> AsyncQuery<T, C> for Typed<E>
+where
+    E: TypedEndpoint<Response = T> + Sync,
+    T: Send,
+    C: AsyncClient + Sync,
+{
+    async_queryer!("request");
+    async_queryer!("query");
+    async_queryer!("send");
+
+    async fn finalise_async(&self, response: Response<Bytes>) -> Result<T, APIError<C::Error>> {
+        if !response.status().is_success() && !self.0.ignore_errors() {
+            Err(APIErrorKind::from_response(response))?
+        } else {
+            Ok(self.0.decode(response)?)
+        }
+    }
+}
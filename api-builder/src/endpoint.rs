@@ -1,10 +1,14 @@
+use core::time::Duration;
 use std::borrow::Cow;
 
 use bytes::Bytes;
 use http::{HeaderMap, Method, Response};
 use serde::de::DeserializeOwned;
 
-use crate::{BodyError, HeaderError, QueryParamPairs};
+use crate::{
+    APIError, AsyncClient, AsyncQuery, BodyError, Client, EndpointBody, HeaderError, Query,
+    QueryParamPairs, RequestBody, RequestTarget, Typed,
+};
 
 /// A trait for providing the necessary information for a single REST API endpoint
 pub trait Endpoint {
@@ -27,34 +31,66 @@ pub trait Endpoint {
     }
 
     /// The query parameters for the endpoint.
-    fn query_params(&self) -> Option<QueryParamPairs> {
+    ///
+    /// These may borrow from the endpoint, e.g. `params.push(("name", &self.name))`.
+    fn query_params(&self) -> Option<QueryParamPairs<'_>> {
+        None
+    }
+
+    /// How long to wait for the response before giving up.
+    ///
+    /// This is attached to the request as a [`RequestTimeout`](crate::RequestTimeout) extension.
+    fn timeout(&self) -> Option<Duration> {
         None
     }
 
+    /// The path and query, relative to the base URL of the client.
+    ///
+    /// Defaults to the segments of [`Endpoint::path`] and [`Endpoint::query_params`].
+    /// Override this to append values such as IDs with [`RequestTarget::segment`], which escapes them.
+    fn target(&self) -> RequestTarget<'_> {
+        RequestTarget::from_path(self.path()).query(self.query_params())
+    }
+
+    /// Writes the full URL, including query, to the end of `url`.
+    ///
+    /// The query is encoded straight into `url`, so the same buffer can be reused for each request.
+    /// Nothing is written if the target is invalid, see [`RequestTarget::write`].
+    #[deprecated(note = "requests are built from `Endpoint::target`, so override that instead")]
+    fn write_url(&self, url: &mut String) {
+        let start = url.len();
+        if self.target().write(url).is_err() {
+            url.truncate(start);
+        }
+    }
+
     /// Builds the full URL, including query.
+    #[deprecated(note = "requests are built from `Endpoint::target`, so override that instead")]
+    #[allow(deprecated)]
     fn url(&self) -> String {
-        let mut path = self.path().to_string();
-        if let Some(query) = self.query_params() {
-            let mut serializer = ::url::form_urlencoded::Serializer::new(String::new());
-            for pair in query.iter() {
-                serializer.append_pair(&pair.key, &pair.value);
-            }
-            let encoded = serializer.finish();
-            if !encoded.is_empty() {
-                path.push('?');
-                path.push_str(&encoded);
-            }
-        }
-        path
+        let mut url = String::new();
+        self.write_url(&mut url);
+        url
     }
 
     /// The body for the endpoint.
     ///
     /// Returns the `Content-Encoding` header for the data as well as the data itself.
+    #[allow(clippy::type_complexity)]
     fn body(&self) -> Result<Option<(Cow<'static, str>, Vec<u8>)>, BodyError> {
         Ok(None)
     }
 
+    /// The body for the endpoint, which may be streamed.
+    ///
+    /// Override this instead of [`Endpoint::body`] to send a file, reader or stream without buffering it.
+    /// Defaults to [`Endpoint::body`].
+    fn request_body(&self) -> Result<Option<EndpointBody>, BodyError> {
+        Ok(self
+            .body()?
+            .map(|(mime, body)| (mime, RequestBody::Bytes(body))))
+    }
+
     /// Deserialize the response bytes.
     ///
     /// Defaults to using `serde_json::from_slice`.
@@ -62,3 +98,140 @@ pub trait Endpoint {
         Ok(serde_json::from_slice(response.body())?)
     }
 }
+
+impl<E> Endpoint for &E
+where
+    E: Endpoint + ?Sized,
+{
+    fn ignore_errors(&self) -> bool {
+        (**self).ignore_errors()
+    }
+
+    fn method(&self) -> Method {
+        (**self).method()
+    }
+
+    fn path(&self) -> Cow<'static, str> {
+        (**self).path()
+    }
+
+    fn headers(&self) -> Result<Option<HeaderMap>, HeaderError> {
+        (**self).headers()
+    }
+
+    fn query_params(&self) -> Option<QueryParamPairs<'_>> {
+        (**self).query_params()
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        (**self).timeout()
+    }
+
+    fn target(&self) -> RequestTarget<'_> {
+        (**self).target()
+    }
+
+    #[allow(deprecated)]
+    fn write_url(&self, url: &mut String) {
+        (**self).write_url(url)
+    }
+
+    #[allow(deprecated)]
+    fn url(&self) -> String {
+        (**self).url()
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn body(&self) -> Result<Option<(Cow<'static, str>, Vec<u8>)>, BodyError> {
+        (**self).body()
+    }
+
+    fn request_body(&self) -> Result<Option<EndpointBody>, BodyError> {
+        (**self).request_body()
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, response: Response<Bytes>) -> Result<T, BodyError> {
+        (**self).deserialize(response)
+    }
+}
+
+/// An [`Endpoint`] which knows the type of its response.
+///
+/// Usually implemented with `#[api_endpoint(response = MyType)]`, which decodes the response as JSON,
+/// or as Protobuf when `prost_response = true` is also given.
+/// The response type is then inferred by [`TypedEndpoint::execute`] and [`TypedEndpoint::execute_async`],
+/// while [`Query`] can still be used to decode the response as any other type.
+pub trait TypedEndpoint: Endpoint {
+    /// The type of a successful response.
+    type Response;
+
+    /// Decode the response bytes.
+    fn decode(&self, response: Response<Bytes>) -> Result<Self::Response, BodyError>;
+
+    /// Perform the query against the client, returning [`TypedEndpoint::Response`].
+    fn execute<C>(&self, client: &C) -> Result<Self::Response, APIError<C::Error>>
+    where
+        C: Client,
+    {
+        Typed(self).query(client)
+    }
+
+    /// Perform the query asynchronously against the client, returning [`TypedEndpoint::Response`].
+    #[cfg(not(target_arch = "wasm32"))]
+    fn execute_async<C>(
+        &self,
+        client: &C,
+    ) -> impl Future<Output = Result<Self::Response, APIError<C::Error>>> + Send
+    where
+        Self: Sync,
+        Self::Response: Send,
+        C: AsyncClient + Sync,
+        C::Error: core::error::Error + Sync + Send + 'static,
+    {
+        async move { Typed(self).query_async(client).await }
+    }
+    /// Perform the query asynchronously against the client, returning [`TypedEndpoint::Response`].
+    #[cfg(target_arch = "wasm32")]
+    fn execute_async<C>(
+        &self,
+        client: &C,
+    ) -> impl Future<Output = Result<Self::Response, APIError<C::Error>>>
+    where
+        Self: Sync,
+        Self::Response: Send,
+        C: AsyncClient + Sync,
+        C::Error: core::error::Error + Sync + Send + 'static,
+    {
+        async move { Typed(self).query_async(client).await }
+    }
+}
+
+impl<E> TypedEndpoint for &E
+where
+    E: TypedEndpoint + ?Sized,
+{
+    type Response = E::Response;
+
+    fn decode(&self, response: Response<Bytes>) -> Result<Self::Response, BodyError> {
+        (**self).decode(response)
+    }
+}
+
+/// The method and path template of an [`Endpoint`], e.g. `GET` and `/pets/{id}`.
+///
+/// Implemented by `#[api_endpoint(...)]` when the path is a string literal or a `format!` of fields,
+/// so that `#[derive(DescribeEndpoint)]` doesn't need to repeat them.
+pub trait EndpointRoute {
+    /// The method for the endpoint.
+    const METHOD: Method;
+    /// The path template, with fields in braces.
+    const PATH: &'static str;
+}
+
+impl<E> EndpointRoute for &E
+where
+    E: EndpointRoute + ?Sized,
+{
+    const METHOD: Method = E::METHOD;
+    const PATH: &'static str = E::PATH;
+}
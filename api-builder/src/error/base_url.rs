@@ -0,0 +1,14 @@
+/// Errors that can occur when resolving a [`BaseUrl`](crate::BaseUrl).
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum BaseUrlError {
+    #[error("failed to parse the base URL: {0}")]
+    Parse(#[from] url::ParseError),
+    #[error("the base URL cannot have a path appended: {0}")]
+    CannotBeABase(String),
+    #[error("the environment variable `{0}` is not set")]
+    MissingVariable(String),
+    #[error("unknown environment: {0}")]
+    UnknownEnvironment(String),
+    #[error("no base URLs were given")]
+    NoHosts,
+}
@@ -7,6 +7,12 @@ pub enum BodyError {
     Build,
     #[error("failed to deserialize")]
     Deserialize,
+    #[error("the body can only be read asynchronously")]
+    AsyncOnly,
+    #[error("files and blocking readers can only be streamed with the `tokio` feature")]
+    BlockingOnly,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
     #[error(transparent)]
     SerdeJson(#[from] serde_json::Error),
     #[error(transparent)]
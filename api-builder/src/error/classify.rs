@@ -0,0 +1,361 @@
+use core::{error::Error, time::Duration};
+use std::io::{self, ErrorKind};
+
+use bytes::Bytes;
+use http::{HeaderMap, Response, StatusCode, header::RETRY_AFTER};
+
+use crate::{APIError, APIErrorKind};
+
+/// The statuses which may succeed if the request is sent again.
+const RETRYABLE_STATUSES: &[StatusCode] = &[
+    StatusCode::REQUEST_TIMEOUT,
+    StatusCode::TOO_EARLY,
+    StatusCode::TOO_MANY_REQUESTS,
+    StatusCode::BAD_GATEWAY,
+    StatusCode::SERVICE_UNAVAILABLE,
+    StatusCode::GATEWAY_TIMEOUT,
+];
+
+/// Headers holding how many requests are left before being rate limited.
+const RATE_LIMIT_REMAINING: &[&str] = &["ratelimit-remaining", "x-ratelimit-remaining"];
+
+/// The kind of the first [`io::Error`] within `error` and its sources.
+pub(crate) fn io_error_kind(error: &(dyn Error + 'static)) -> Option<ErrorKind> {
+    core::iter::successors(Some(error), |x| (*x).source())
+        .find_map(|x| x.downcast_ref::<io::Error>())
+        .map(io::Error::kind)
+}
+
+/// Whether an I/O error of `kind` means a connection couldn't be made.
+pub(crate) fn is_connect_kind(kind: ErrorKind) -> bool {
+    matches!(
+        kind,
+        ErrorKind::ConnectionRefused
+            | ErrorKind::AddrNotAvailable
+            | ErrorKind::HostUnreachable
+            | ErrorKind::NetworkUnreachable
+            | ErrorKind::NotConnected
+    )
+}
+
+/// Whether an I/O error of `kind` is likely to be temporary.
+pub(crate) fn is_transient_kind(kind: ErrorKind) -> bool {
+    is_connect_kind(kind)
+        || matches!(
+            kind,
+            ErrorKind::TimedOut
+                | ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::BrokenPipe
+                | ErrorKind::UnexpectedEof
+        )
+}
+
+/// Parse `Retry-After`, which is either a number of seconds or an HTTP date.
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    // The current time isn't available everywhere on wasm
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let date = parse_http_date(value)?;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?;
+        Some(Duration::from_secs(date.saturating_sub(now.as_secs())))
+    }
+    #[cfg(target_arch = "wasm32")]
+    None
+}
+
+/// Parse an HTTP date such as `Sun, 06 Nov 1994 08:49:37 GMT`, as seconds since the Unix epoch.
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_http_date(value: &str) -> Option<u64> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let Ok([_, day, month, year, time, "GMT"]) =
+        <[&str; 6]>::try_from(value.split_whitespace().collect::<Vec<_>>())
+    else {
+        return None;
+    };
+    let day: i64 = day.parse().ok()?;
+    let month = MONTHS.iter().position(|x| *x == month)? as i64 + 1;
+    let year: i64 = year.parse().ok()?;
+    let [hour, minute, second] = time
+        .split(':')
+        .map(|x| x.parse::<i64>().ok())
+        .collect::<Option<Vec<_>>>()?
+        .try_into()
+        .ok()?;
+
+    // Days since the epoch, counting years from March so the leap day is last
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    u64::try_from(days * 86_400 + hour * 3_600 + minute * 60 + second).ok()
+}
+
+/// Classification, so retries, alerts and metrics make the same decisions.
+///
+/// [`APIErrorKind::Client`] is never classified, as nothing is known about `E`.
+impl<E> APIErrorKind<E> {
+    /// The status of the response, if the server responded.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::Response(response)
+            | Self::Problem { response, .. }
+            | Self::PreconditionFailed(response) => Some(response.status()),
+            Self::HttpClient(e) => e.status(),
+            Self::Shared(e) => e.status(),
+            Self::Client(_)
+            | Self::Http(_)
+            | Self::Body(_)
+            | Self::Header(_)
+            | Self::Timeout(_)
+            | Self::CircuitOpen(_)
+            | Self::AsyncContext
+            | Self::Target(_)
+            | Self::URL(_)
+            | Self::BaseUrl(_)
+            | Self::Other(_) => None,
+        }
+    }
+
+    /// The body of the response, if the server responded.
+    pub fn response_body(&self) -> Option<&Bytes> {
+        self.response().map(Response::body)
+    }
+
+    /// Whether the request timed out, either within the HTTP client or from [`RequestTimeout`](crate::RequestTimeout).
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            Self::Timeout(_) => true,
+            Self::HttpClient(e) => e.is_timeout(),
+            Self::Shared(e) => e.is_timeout(),
+            Self::Other(e) => io_error_kind(e.as_ref()) == Some(ErrorKind::TimedOut),
+            Self::Client(_)
+            | Self::Http(_)
+            | Self::Body(_)
+            | Self::Header(_)
+            | Self::Response(_)
+            | Self::Problem { .. }
+            | Self::PreconditionFailed(_)
+            | Self::CircuitOpen(_)
+            | Self::AsyncContext
+            | Self::Target(_)
+            | Self::URL(_)
+            | Self::BaseUrl(_) => false,
+        }
+    }
+
+    /// Whether a connection couldn't be made, so the request wasn't sent.
+    pub fn is_connect(&self) -> bool {
+        match self {
+            Self::HttpClient(e) => e.is_connect(),
+            Self::Shared(e) => e.is_connect(),
+            Self::Other(e) => io_error_kind(e.as_ref()).is_some_and(is_connect_kind),
+            Self::Client(_)
+            | Self::Http(_)
+            | Self::Body(_)
+            | Self::Header(_)
+            | Self::Response(_)
+            | Self::Problem { .. }
+            | Self::PreconditionFailed(_)
+            | Self::Timeout(_)
+            | Self::CircuitOpen(_)
+            | Self::AsyncContext
+            | Self::Target(_)
+            | Self::URL(_)
+            | Self::BaseUrl(_) => false,
+        }
+    }
+
+    /// Whether the server responded with a `4xx` status.
+    pub fn is_client_error(&self) -> bool {
+        self.status().is_some_and(|x| x.is_client_error())
+    }
+
+    /// Whether the server responded with a `5xx` status.
+    pub fn is_server_error(&self) -> bool {
+        self.status().is_some_and(|x| x.is_server_error())
+    }
+
+    /// Whether the server rejected the request for being sent too often.
+    ///
+    /// This is `429 Too Many Requests`, or `403` and `503` when `RateLimit-Remaining` or `X-RateLimit-Remaining` is `0`.
+    pub fn is_rate_limited(&self) -> bool {
+        match self.status() {
+            Some(StatusCode::TOO_MANY_REQUESTS) => true,
+            Some(StatusCode::FORBIDDEN | StatusCode::SERVICE_UNAVAILABLE) => {
+                self.response().is_some_and(|response| {
+                    RATE_LIMIT_REMAINING.iter().any(|name| {
+                        response
+                            .headers()
+                            .get(*name)
+                            .and_then(|x| x.to_str().ok())
+                            .is_some_and(|x| x.trim() == "0")
+                    })
+                })
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether sending the request again may succeed, ignoring whether it is safe to.
+    ///
+    /// This covers timeouts, connection failures, an open circuit, rate limiting, and the `408`, `425`, `429`, `502`, `503` and `504` statuses.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Response(_) | Self::Problem { .. } => {
+                self.status()
+                    .is_some_and(|x| RETRYABLE_STATUSES.contains(&x))
+                    || self.is_rate_limited()
+            }
+            Self::HttpClient(e) => match e.status() {
+                Some(status) => RETRYABLE_STATUSES.contains(&status),
+                None => e.is_transient(),
+            },
+            Self::Timeout(_) | Self::CircuitOpen(_) => true,
+            Self::Shared(e) => e.is_retryable(),
+            Self::Other(e) => io_error_kind(e.as_ref()).is_some_and(is_transient_kind),
+            // The resource must be fetched again first
+            Self::PreconditionFailed(_) => false,
+            Self::Client(_)
+            | Self::Http(_)
+            | Self::Body(_)
+            | Self::Header(_)
+            | Self::AsyncContext
+            | Self::Target(_)
+            | Self::URL(_)
+            | Self::BaseUrl(_) => false,
+        }
+    }
+
+    /// How long the server asked to wait before sending the request again, from `Retry-After`.
+    pub fn retry_after(&self) -> Option<Duration> {
+        parse_retry_after(self.response()?.headers())
+    }
+}
+
+impl<E> APIError<E> {
+    /// The status of the response, if the server responded.
+    pub fn status(&self) -> Option<StatusCode> {
+        self.kind().status()
+    }
+
+    /// The body of the response, if the server responded.
+    pub fn response_body(&self) -> Option<&Bytes> {
+        self.kind().response_body()
+    }
+
+    /// Whether the request timed out, see [`APIErrorKind::is_timeout`].
+    pub fn is_timeout(&self) -> bool {
+        self.kind().is_timeout()
+    }
+
+    /// Whether a connection couldn't be made, see [`APIErrorKind::is_connect`].
+    pub fn is_connect(&self) -> bool {
+        self.kind().is_connect()
+    }
+
+    /// Whether the server responded with a `4xx` status.
+    pub fn is_client_error(&self) -> bool {
+        self.kind().is_client_error()
+    }
+
+    /// Whether the server responded with a `5xx` status.
+    pub fn is_server_error(&self) -> bool {
+        self.kind().is_server_error()
+    }
+
+    /// Whether the server rejected the request for being sent too often, see [`APIErrorKind::is_rate_limited`].
+    pub fn is_rate_limited(&self) -> bool {
+        self.kind().is_rate_limited()
+    }
+
+    /// Whether sending the request again may succeed, see [`APIErrorKind::is_retryable`].
+    pub fn is_retryable(&self) -> bool {
+        self.kind().is_retryable()
+    }
+
+    /// How long the server asked to wait before sending the request again, from `Retry-After`.
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.kind().retry_after()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn parses_http_dates() {
+        for (date, seconds) in [
+            ("Sun, 06 Nov 1994 08:49:37 GMT", 784_111_777),
+            ("Thu, 01 Jan 1970 00:00:00 GMT", 0),
+            ("Tue, 29 Feb 2000 00:00:00 GMT", 951_782_400),
+            ("Tue, 31 Dec 2024 23:59:59 GMT", 1_735_689_599),
+            ("Mon, 01 Mar 2100 00:00:00 GMT", 4_107_542_400),
+        ] {
+            assert_eq!(parse_http_date(date), Some(seconds), "{date}");
+        }
+
+        for date in [
+            "Sun, 06 Nov 1994 08:49:37 UTC",
+            "Sun, 06 November 1994 08:49:37 GMT",
+            "Sun, 06 Nov 1994 08:49 GMT",
+            "Wed, 31 Dec 1969 23:59:59 GMT",
+            "Sunday, 06-Nov-94 08:49:37 GMT",
+        ] {
+            assert_eq!(parse_http_date(date), None, "{date}");
+        }
+    }
+
+    #[test]
+    fn parses_retry_after_seconds() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, "120".parse().unwrap());
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(120)));
+
+        headers.insert(
+            RETRY_AFTER,
+            "Sun, 06 Nov 1994 08:49:37 GMT".parse().unwrap(),
+        );
+        #[cfg(not(target_arch = "wasm32"))]
+        assert_eq!(parse_retry_after(&headers), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn classifies_io_errors() {
+        let kind = |kind| APIErrorKind::<()>::Other(io::Error::from(kind).into());
+        for connect in [
+            ErrorKind::ConnectionRefused,
+            ErrorKind::AddrNotAvailable,
+            ErrorKind::HostUnreachable,
+            ErrorKind::NetworkUnreachable,
+            ErrorKind::NotConnected,
+        ] {
+            assert!(kind(connect).is_connect(), "{connect}");
+            assert!(kind(connect).is_retryable(), "{connect}");
+        }
+        for transient in [
+            ErrorKind::TimedOut,
+            ErrorKind::ConnectionReset,
+            ErrorKind::UnexpectedEof,
+        ] {
+            assert!(!kind(transient).is_connect(), "{transient}");
+            assert!(kind(transient).is_retryable(), "{transient}");
+        }
+        assert!(!kind(ErrorKind::PermissionDenied).is_retryable());
+    }
+}
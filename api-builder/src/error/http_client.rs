@@ -1,3 +1,10 @@
+use core::error::Error;
+use std::io::ErrorKind;
+
+use http::StatusCode;
+
+use super::classify::{io_error_kind, is_connect_kind, is_transient_kind};
+
 // Errors that can occur from HTTP clients.
 #[derive(Debug, thiserror::Error)]
 pub enum HttpClientError {
@@ -5,7 +12,18 @@ pub enum HttpClientError {
     #[cfg(feature = "reqwest")]
     #[error(transparent)]
     Reqwest(#[from] reqwest::Error),
-    /// There was an error with `rquest`.
+    /// There was an error with `hyper`.
+    #[cfg(feature = "hyper")]
+    #[error(transparent)]
+    Hyper(#[from] hyper::Error),
+    /// There was an error with the `hyper-util` client.
+    #[cfg(feature = "hyper")]
+    #[error(transparent)]
+    HyperClient(#[from] hyper_util::client::legacy::Error),
+    /// There was an error with `ureq`.
+    #[cfg(feature = "ureq")]
+    #[error(transparent)]
+    Ureq(#[from] ureq::Error),
     /// There was an error with `gloo-net`.
     #[cfg(target_arch = "wasm32")]
     #[error(transparent)]
@@ -14,3 +32,77 @@ pub enum HttpClientError {
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
+impl HttpClientError {
+    /// The inner error, whose sources are searched for an [`std::io::Error`].
+    fn inner(&self) -> &(dyn Error + 'static) {
+        match self {
+            #[cfg(feature = "reqwest")]
+            Self::Reqwest(e) => e,
+            #[cfg(feature = "hyper")]
+            Self::Hyper(e) => e,
+            #[cfg(feature = "hyper")]
+            Self::HyperClient(e) => e,
+            // `ureq` doesn't expose the source of its errors
+            #[cfg(feature = "ureq")]
+            Self::Ureq(ureq::Error::Io(e)) => e,
+            #[cfg(feature = "ureq")]
+            Self::Ureq(e) => e,
+            #[cfg(target_arch = "wasm32")]
+            Self::GlooNet(e) => e,
+            Self::Other(e) => e.as_ref(),
+        }
+    }
+
+    /// The status of the response, for clients which turn unsuccessful responses into errors.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            #[cfg(feature = "reqwest")]
+            Self::Reqwest(e) => e.status(),
+            #[cfg(feature = "ureq")]
+            Self::Ureq(ureq::Error::StatusCode(status)) => StatusCode::from_u16(*status).ok(),
+            _ => None,
+        }
+    }
+
+    /// Whether the request timed out.
+    pub fn is_timeout(&self) -> bool {
+        let timeout = match self {
+            #[cfg(feature = "reqwest")]
+            Self::Reqwest(e) => e.is_timeout(),
+            #[cfg(feature = "hyper")]
+            Self::Hyper(e) => e.is_timeout(),
+            #[cfg(feature = "ureq")]
+            Self::Ureq(e) => matches!(e, ureq::Error::Timeout(_)),
+            _ => false,
+        };
+        timeout || io_error_kind(self.inner()) == Some(ErrorKind::TimedOut)
+    }
+
+    /// Whether a connection couldn't be made, so the request wasn't sent.
+    pub fn is_connect(&self) -> bool {
+        let connect = match self {
+            #[cfg(feature = "reqwest")]
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Reqwest(e) => e.is_connect(),
+            #[cfg(feature = "hyper")]
+            Self::HyperClient(e) => e.is_connect(),
+            #[cfg(feature = "ureq")]
+            Self::Ureq(e) => matches!(e, ureq::Error::ConnectionFailed | ureq::Error::HostNotFound),
+            _ => false,
+        };
+        connect || io_error_kind(self.inner()).is_some_and(is_connect_kind)
+    }
+
+    /// Whether the error is likely to be temporary, such as a timeout or the connection being reset.
+    pub fn is_transient(&self) -> bool {
+        let transient = match self {
+            #[cfg(feature = "hyper")]
+            Self::Hyper(e) => e.is_incomplete_message(),
+            _ => false,
+        };
+        transient
+            || self.is_timeout()
+            || self.is_connect()
+            || io_error_kind(self.inner()).is_some_and(is_transient_kind)
+    }
+}
@@ -1,19 +1,54 @@
-use core::{error::Error, fmt};
+use core::{error::Error, fmt, time::Duration};
+use std::sync::Arc;
 
 use bytes::Bytes;
 use http::Response;
 
-import!(body, header, http_client);
+import!(
+    base_url,
+    body,
+    context,
+    header,
+    http_client,
+    problem,
+    target,
+);
+mod classify;
+
+/// The longest response body shown when displaying an error, in characters.
+const BODY_PREVIEW: usize = 200;
 
 pub struct APIError<E>(Box<Inner<E>>);
 impl<E> APIError<E> {
     pub fn new(kind: APIErrorKind<E>) -> Self {
-        Self(Box::new(Inner { kind }))
+        Self(Box::new(Inner {
+            kind,
+            context: ErrorContext::default(),
+        }))
     }
 
     pub fn kind(&self) -> &APIErrorKind<E> {
         &self.0.kind
     }
+
+    pub fn into_kind(self) -> APIErrorKind<E> {
+        self.0.kind
+    }
+
+    /// What was being called when the error occurred.
+    pub fn context(&self) -> &ErrorContext {
+        &self.0.context
+    }
+
+    /// What was being called when the error occurred.
+    pub fn context_mut(&mut self) -> &mut ErrorContext {
+        &mut self.0.context
+    }
+
+    /// The problem details returned by the server, if any.
+    pub fn problem(&self) -> Option<&ProblemDetails> {
+        self.kind().problem()
+    }
 }
 impl<E, T> From<T> for APIError<E>
 where
@@ -28,21 +63,93 @@ where
     E: fmt::Debug,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.kind().fmt(f)
+        if self.context().is_empty() {
+            return self.kind().fmt(f);
+        }
+        f.debug_struct("APIError")
+            .field("kind", self.kind())
+            .field("context", self.context())
+            .finish()
     }
 }
+/// Displays the error, its status, what was being called and a preview of the response body.
+///
+/// For example, `the server returned an error (404 Not Found) for GET https://example.com/pets/1 (GetPet, after 120ms): {"message":"not found"}`.
 impl<E> fmt::Display for APIError<E>
 where
     E: fmt::Display,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.kind().fmt(f)
+        // The shared error shows its own status and body
+        if let APIErrorKind::Shared(e) = self.kind() {
+            e.fmt(f)?;
+            return context::write_context(f, self.context());
+        }
+
+        self.kind().fmt(f)?;
+        let response = self.kind().response();
+        if let Some(response) = response {
+            write!(f, " ({})", response.status())?;
+        }
+        context::write_context(f, self.context())?;
+
+        // Problem details are already shown
+        if let (Some(response), None) = (response, self.kind().problem()) {
+            write_body_preview(f, response.body())?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the start of a response body, with its whitespace collapsed.
+fn write_body_preview(f: &mut fmt::Formatter<'_>, body: &[u8]) -> fmt::Result {
+    // Only the start of the body is needed, however large it is
+    let mut preview = &body[..body.len().min(BODY_PREVIEW * 4)];
+    if let Err(e) = core::str::from_utf8(preview)
+        && e.error_len().is_none()
+    {
+        // Don't split the last character
+        preview = &preview[..e.valid_up_to()];
+    }
+    let mut truncated = preview.len() < body.len();
+    let preview = String::from_utf8_lossy(preview);
+
+    let mut remaining = BODY_PREVIEW;
+    let mut words = preview.split_whitespace().peekable();
+    if words.peek().is_none() {
+        return Ok(());
+    }
+    f.write_str(": ")?;
+    for (i, word) in words.enumerate() {
+        if i > 0 {
+            if remaining == 0 {
+                truncated = true;
+                break;
+            }
+            f.write_str(" ")?;
+            remaining -= 1;
+        }
+        let end = word
+            .char_indices()
+            .nth(remaining)
+            .map_or(word.len(), |(i, _)| i);
+        f.write_str(&word[..end])?;
+        if end < word.len() {
+            truncated = true;
+            break;
+        }
+        remaining -= word.chars().count();
+    }
+    if truncated {
+        f.write_str("…")?;
     }
+    Ok(())
 }
 impl<E> Error for APIError<E> where E: fmt::Display + fmt::Debug {}
 
 struct Inner<E> {
     kind: APIErrorKind<E>,
+    context: ErrorContext,
 }
 
 /// Implementing this trait will also implement [`From<E>`] for [`APIErrorKind<E>`], creating [`APIErrorKind::Client`].
@@ -72,14 +179,71 @@ pub enum APIErrorKind<E> {
     /// The server returned a error.
     #[error("the server returned an error")]
     Response(Response<Bytes>),
+    /// The server returned a error, explained with `application/problem+json`.
+    #[error("the server returned an error: {details}")]
+    Problem {
+        details: ProblemDetails,
+        response: Response<Bytes>,
+    },
+    /// The server rejected a conditional request, as the resource was modified.
+    #[error("the precondition failed, the resource has been modified")]
+    PreconditionFailed(Response<Bytes>),
+    /// The request timed out.
+    #[error("the request timed out after {0:?}")]
+    Timeout(Duration),
+    /// The circuit breaker is open, so the request was not sent.
+    #[error("the circuit breaker is open for {0}")]
+    CircuitOpen(String),
+    /// A blocking call was made from within an async runtime, e.g. by `BlockOn`.
+    #[error("cannot block on a request from within an async runtime")]
+    AsyncContext,
+    /// An error which was shared between several callers, e.g. by [`CoalescingClient`](crate::CoalescingClient).
+    #[error(transparent)]
+    Shared(Arc<APIError<E>>),
+    /// The path or query of the request is invalid.
+    #[error(transparent)]
+    Target(#[from] TargetError),
     /// URL parsing failed.
     #[error("failed to parse URL")]
     URL(#[from] url::ParseError),
+    /// The base URL of the client is missing or invalid.
+    #[error(transparent)]
+    BaseUrl(#[from] BaseUrlError),
     /// There was an unknown error.
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
 impl<E> APIErrorKind<E> {
+    /// Create an error from a unsuccessful response.
+    ///
+    /// If the response contains `application/problem+json`, [`APIErrorKind::Problem`] is returned.
+    /// Otherwise, [`APIErrorKind::Response`] is returned.
+    pub fn from_response(response: Response<Bytes>) -> Self {
+        match ProblemDetails::from_response(&response) {
+            Some(details) => Self::Problem { details, response },
+            None => Self::Response(response),
+        }
+    }
+
+    /// The unsuccessful response returned by the server, if any.
+    pub fn response(&self) -> Option<&Response<Bytes>> {
+        match self {
+            APIErrorKind::Response(response)
+            | APIErrorKind::Problem { response, .. }
+            | APIErrorKind::PreconditionFailed(response) => Some(response),
+            APIErrorKind::Shared(e) => e.kind().response(),
+            _ => None,
+        }
+    }
+
+    /// The problem details returned by the server, if any.
+    pub fn problem(&self) -> Option<&ProblemDetails> {
+        match self {
+            APIErrorKind::Problem { details, .. } => Some(details),
+            _ => None,
+        }
+    }
+
     /// Convert an [`APIErrorKind<T>`] to [`APIErrorKind<E>`].
     pub fn from_api_error<T: Into<E>>(err: APIErrorKind<T>) -> APIErrorKind<E> {
         match err {
@@ -89,11 +253,52 @@ impl<E> APIErrorKind<E> {
             APIErrorKind::Body(e) => APIErrorKind::Body(e),
             APIErrorKind::Header(e) => APIErrorKind::Header(e),
             APIErrorKind::Response(e) => APIErrorKind::Response(e),
+            APIErrorKind::Problem { details, response } => {
+                APIErrorKind::Problem { details, response }
+            }
+            APIErrorKind::PreconditionFailed(e) => APIErrorKind::PreconditionFailed(e),
+            APIErrorKind::Timeout(e) => APIErrorKind::Timeout(e),
+            APIErrorKind::CircuitOpen(e) => APIErrorKind::CircuitOpen(e),
+            APIErrorKind::AsyncContext => APIErrorKind::AsyncContext,
+            APIErrorKind::Shared(e) => match Arc::try_unwrap(e) {
+                Ok(e) => Self::from_api_error(e.into_kind()),
+                Err(e) => Self::from_shared(e.kind()),
+            },
+            APIErrorKind::Target(e) => APIErrorKind::Target(e),
             APIErrorKind::URL(e) => APIErrorKind::URL(e),
+            APIErrorKind::BaseUrl(e) => APIErrorKind::BaseUrl(e),
             APIErrorKind::Other(e) => APIErrorKind::Other(e),
         }
     }
 
+    /// Convert an error still held by other callers, copying what can be copied.
+    ///
+    /// Responses are cloned, while errors which can't be cloned become [`APIErrorKind::Other`] with the same message.
+    fn from_shared<T>(err: &APIErrorKind<T>) -> APIErrorKind<E> {
+        let message = |e: &dyn fmt::Display| APIErrorKind::Other(anyhow::anyhow!("{e}"));
+        match err {
+            APIErrorKind::Client(_) => message(&"the client encountered an error"),
+            APIErrorKind::Http(e) => message(e),
+            APIErrorKind::HttpClient(e) => message(e),
+            APIErrorKind::Body(e) => message(e),
+            APIErrorKind::Header(e) => message(e),
+            APIErrorKind::Response(e) => APIErrorKind::Response(e.clone()),
+            APIErrorKind::Problem { details, response } => APIErrorKind::Problem {
+                details: details.clone(),
+                response: response.clone(),
+            },
+            APIErrorKind::PreconditionFailed(e) => APIErrorKind::PreconditionFailed(e.clone()),
+            APIErrorKind::Timeout(e) => APIErrorKind::Timeout(*e),
+            APIErrorKind::CircuitOpen(e) => APIErrorKind::CircuitOpen(e.clone()),
+            APIErrorKind::AsyncContext => APIErrorKind::AsyncContext,
+            APIErrorKind::Shared(e) => Self::from_shared(e.kind()),
+            APIErrorKind::Target(e) => message(e),
+            APIErrorKind::URL(e) => APIErrorKind::URL(*e),
+            APIErrorKind::BaseUrl(e) => APIErrorKind::BaseUrl(e.clone()),
+            APIErrorKind::Other(e) => APIErrorKind::Other(anyhow::anyhow!("{e:#}")),
+        }
+    }
+
     /// Convert [`APIErrorKind::Client`] to [`APIErrorKind::Other`].
     pub fn from_any_api_error<T: core::error::Error + Sync + Send + 'static>(
         err: APIErrorKind<T>,
@@ -105,7 +310,17 @@ impl<E> APIErrorKind<E> {
             APIErrorKind::Body(e) => APIErrorKind::Body(e),
             APIErrorKind::Header(e) => APIErrorKind::Header(e),
             APIErrorKind::Response(e) => APIErrorKind::Response(e),
+            APIErrorKind::Problem { details, response } => {
+                APIErrorKind::Problem { details, response }
+            }
+            APIErrorKind::PreconditionFailed(e) => APIErrorKind::PreconditionFailed(e),
+            APIErrorKind::Timeout(e) => APIErrorKind::Timeout(e),
+            APIErrorKind::CircuitOpen(e) => APIErrorKind::CircuitOpen(e),
+            APIErrorKind::AsyncContext => APIErrorKind::AsyncContext,
+            APIErrorKind::Shared(e) => APIErrorKind::Other(anyhow::Error::new(e)),
+            APIErrorKind::Target(e) => APIErrorKind::Target(e),
             APIErrorKind::URL(e) => APIErrorKind::URL(e),
+            APIErrorKind::BaseUrl(e) => APIErrorKind::BaseUrl(e),
             APIErrorKind::Other(e) => APIErrorKind::Other(e),
         }
     }
@@ -123,7 +338,7 @@ impl<E> APIErrorKind<E> {
 
 impl<E> From<Response<Bytes>> for APIErrorKind<E> {
     fn from(value: Response<Bytes>) -> Self {
-        Self::Response(value)
+        Self::from_response(value)
     }
 }
 impl<E: APIClientError> From<E> for APIErrorKind<E> {
@@ -148,5 +363,76 @@ impl_error_conv!(Header, HeaderError, ToStr, http::header::ToStrError);
 
 #[cfg(feature = "reqwest")]
 impl_error_conv!(HttpClient, HttpClientError, Reqwest, reqwest::Error);
+#[cfg(feature = "hyper")]
+impl_error_conv!(HttpClient, HttpClientError, Hyper, hyper::Error);
+#[cfg(feature = "hyper")]
+impl_error_conv!(
+    HttpClient,
+    HttpClientError,
+    HyperClient,
+    hyper_util::client::legacy::Error
+);
+#[cfg(feature = "ureq")]
+impl_error_conv!(HttpClient, HttpClientError, Ureq, ureq::Error);
 #[cfg(target_arch = "wasm32")]
 impl_error_conv!(HttpClient, HttpClientError, GlooNet, gloo_net::Error);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Convert a shared error while another caller still holds it.
+    fn convert_shared(kind: APIErrorKind<&'static str>) -> APIErrorKind<String> {
+        let shared = Arc::new(APIError::new(kind));
+        let _other = shared.clone();
+        APIErrorKind::from_api_error(APIErrorKind::Shared(shared))
+    }
+
+    #[test]
+    fn converts_shared_errors_with_other_owners() {
+        let response = Response::builder()
+            .status(503)
+            .body(Bytes::from_static(b"busy"))
+            .unwrap();
+        let APIErrorKind::Response(response) = convert_shared(APIErrorKind::Response(response))
+        else {
+            panic!("the response was lost");
+        };
+        assert_eq!(response.status(), 503);
+        assert_eq!(response.body(), "busy");
+
+        let kind = convert_shared(APIErrorKind::Timeout(Duration::from_secs(1)));
+        assert!(kind.is_timeout());
+        let kind = convert_shared(anyhow::anyhow!("refused").into());
+        assert_eq!(kind.to_string(), "refused");
+    }
+
+    #[test]
+    fn previews_bodies() {
+        struct Preview<'a>(&'a [u8]);
+        impl fmt::Display for Preview<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write_body_preview(f, self.0)
+            }
+        }
+        let preview = |body: &[u8]| Preview(body).to_string();
+
+        assert_eq!(preview(b""), "");
+        assert_eq!(preview(b" \n\t "), "");
+        assert_eq!(
+            preview(b" service\n\n  unavailable "),
+            ": service unavailable"
+        );
+
+        let long = "word ".repeat(1_000_000);
+        let expected = format!(": {}…", &long[..BODY_PREVIEW]);
+        assert_eq!(preview(long.as_bytes()), expected);
+
+        // A character split by the end of the preview is dropped, not replaced
+        let mut wide = "é".repeat(BODY_PREVIEW * 2 - 1);
+        wide.push('a');
+        wide.push_str(&"é".repeat(10));
+        let expected = format!(": {}…", "é".repeat(BODY_PREVIEW));
+        assert_eq!(preview(wide.as_bytes()), expected);
+    }
+}
@@ -0,0 +1,143 @@
+use core::fmt;
+
+use bytes::Bytes;
+use http::{Response, header::CONTENT_TYPE};
+use serde::{Deserialize, Serialize};
+
+/// A problem details object, as described by [RFC 9457](https://www.rfc-editor.org/rfc/rfc9457) (which obsoletes RFC 7807).
+///
+/// Servers send these with the `application/problem+json` content type to explain why a request failed.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct ProblemDetails {
+    /// A URI reference that identifies the problem type.
+    ///
+    /// When absent, it should be assumed to be `about:blank`, see [`ProblemDetails::type_uri`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<String>,
+    /// A short, human-readable summary of the problem type.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The HTTP status code generated by the origin server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// A human-readable explanation specific to this occurrence of the problem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// A URI reference that identifies the specific occurrence of the problem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// Any extension members.
+    #[serde(flatten)]
+    pub extensions: serde_json::Map<String, serde_json::Value>,
+}
+impl ProblemDetails {
+    /// The content type used for JSON problem details.
+    pub const CONTENT_TYPE: &'static str = "application/problem+json";
+
+    /// The problem type, defaulting to `about:blank`.
+    pub fn type_uri(&self) -> &str {
+        self.r#type.as_deref().unwrap_or("about:blank")
+    }
+
+    /// Get an extension member.
+    pub fn extension(&self, key: &str) -> Option<&serde_json::Value> {
+        self.extensions.get(key)
+    }
+
+    /// Whether the response has the `application/problem+json` content type.
+    pub fn is_problem_response<T>(response: &Response<T>) -> bool {
+        response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .is_some_and(|mime| mime.trim().eq_ignore_ascii_case(Self::CONTENT_TYPE))
+    }
+
+    /// Attempt to parse problem details from a response.
+    ///
+    /// Returns [`None`] if the response does not have the `application/problem+json` content type,
+    /// or if the body is not a valid problem details object.
+    pub fn from_response(response: &Response<Bytes>) -> Option<Self> {
+        if !Self::is_problem_response(response) {
+            return None;
+        }
+
+        serde_json::from_slice(response.body()).ok()
+    }
+}
+impl fmt::Display for ProblemDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.title.as_deref().unwrap_or(self.type_uri()))?;
+        if let Some(status) = self.status {
+            write!(f, " ({status})")?;
+        }
+        if let Some(detail) = &self.detail {
+            write!(f, ": {detail}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::StatusCode;
+
+    use super::*;
+    use crate::APIErrorKind;
+
+    fn response(content_type: &str, body: &'static str) -> Response<Bytes> {
+        Response::builder()
+            .status(StatusCode::FORBIDDEN)
+            .header(CONTENT_TYPE, content_type)
+            .body(Bytes::from_static(body.as_bytes()))
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_problem_responses() {
+        let body = r#"{
+            "type": "https://example.com/probs/out-of-credit",
+            "title": "You do not have enough credit.",
+            "status": 403,
+            "detail": "Your current balance is 30, but that costs 50.",
+            "instance": "/account/12345/msgs/abc",
+            "balance": 30
+        }"#;
+        let kind = APIErrorKind::<()>::from_response(response(
+            "Application/Problem+JSON; charset=utf-8",
+            body,
+        ));
+        let APIErrorKind::Problem { details, response } = &kind else {
+            panic!("expected problem details");
+        };
+        assert_eq!(
+            details.type_uri(),
+            "https://example.com/probs/out-of-credit"
+        );
+        assert_eq!(details.status, Some(403));
+        assert_eq!(details.instance.as_deref(), Some("/account/12345/msgs/abc"));
+        assert_eq!(details.extension("balance"), Some(&serde_json::json!(30)));
+        assert_eq!(
+            details.to_string(),
+            "You do not have enough credit. (403): Your current balance is 30, but that costs 50."
+        );
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(kind.problem(), Some(details));
+    }
+
+    #[test]
+    fn keeps_other_responses() {
+        // Problem details need the content type
+        let kind = APIErrorKind::<()>::from_response(response("application/json", "{}"));
+        assert!(matches!(kind, APIErrorKind::Response(_)));
+
+        // An invalid body keeps the response rather than failing
+        let kind =
+            APIErrorKind::<()>::from_response(response(ProblemDetails::CONTENT_TYPE, "oops"));
+        assert!(matches!(kind, APIErrorKind::Response(_)));
+
+        let details = ProblemDetails::from_response(&response(ProblemDetails::CONTENT_TYPE, "{}"));
+        assert_eq!(details.unwrap().type_uri(), "about:blank");
+    }
+}
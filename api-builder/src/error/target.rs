@@ -0,0 +1,8 @@
+/// Errors that can occur when joining a [`RequestTarget`](crate::RequestTarget) to a base URL.
+#[derive(Debug, thiserror::Error)]
+pub enum TargetError {
+    #[error("the path segment `{0}` would move outside of the base URL")]
+    DotSegment(String),
+    #[error(transparent)]
+    Http(#[from] http::Error),
+}
@@ -9,22 +9,35 @@ macro_rules! import {
 }
 
 import!(
+    base,
     client,
     combinators,
     error,
     endpoint,
     macros,
     query_params,
+    query_value,
     query,
+    request_body,
+    request_target,
 );
 
+#[cfg(feature = "openapi")]
+pub mod openapi;
+
 #[cfg(feature = "derive")]
 pub use api_builder_derive::*;
 
+// The derives refer to `::api_builder`, which tests within this crate need too
+#[cfg(all(test, feature = "derive"))]
+extern crate self as api_builder;
+
 // Re-exports
 pub use bytes::Bytes;
 pub use http::{
-    HeaderMap, Method, Request, Response, StatusCode, request::Builder as RequestBuilder,
+    HeaderMap, Method, Request, Response, StatusCode, Uri, request::Builder as RequestBuilder,
 };
+#[cfg(feature = "openapi")]
+pub use schemars;
 pub use serde_json;
 pub use url::Url;
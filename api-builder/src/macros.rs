@@ -28,6 +28,39 @@ macro_rules! headermap_checked {
     };
 }
 
+/// Runs a query within a [`QueryContext`](crate::QueryContext), adding the endpoint, request and timing to any error.
+///
+/// Takes the query trait, e.g. `sync Query<T, C>` or `async AsyncQuery<T, C>`, whose `request`, `send` and `finalise` methods are called.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! query_in_context {
+    (sync $query:path, $this:expr, $client:expr) => {{
+        let mut context = $crate::QueryContext::new::<Self>();
+        let result = (|| {
+            let request = <Self as $query>::request($this, $client)?;
+            context.request(&request);
+            <Self as $query>::finalise($this, <Self as $query>::send($this, $client, request)?)
+        })();
+        result.map_err(|e| context.attach(e))
+    }};
+    (async $query:path, $this:expr, $client:expr) => {{
+        let mut context = $crate::QueryContext::new::<Self>();
+        let result = async {
+            let request = <Self as $query>::request_async($this, $client).await?;
+            context.request(&request);
+            <Self as $query>::finalise_async(
+                $this,
+                <Self as $query>::send_async($this, $client, request).await?,
+            )
+            .await
+        }
+        .await;
+        result.map_err(|e| context.attach(e))
+    }};
+}
+#[doc(hidden)]
+pub use query_in_context as in_context;
+
 /// A helper trait for implementing [Query](crate::Query) for sync clients.
 ///
 /// If using a combinator, make sure to implement [`Deref`](core::ops::Deref) for the combinator so the methods of the endpoint can be accessed.
@@ -39,12 +72,12 @@ macro_rules! impl_query {
             client: &C,
         ) -> Result<$crate::RequestBuilder, $crate::APIError<C::Error>> {
             let method = self.method();
-            let url = client.rest_endpoint(&self.url())?;
-            let request = $crate::Request::builder()
-                .method(method)
-                .uri(url.to_string());
+            let uri = client.rest_endpoint(&self.target())?;
+            let mut request = $crate::Request::builder().method(method).uri(uri);
+            if let Some(timeout) = self.timeout() {
+                request = request.extension($crate::RequestTimeout(timeout));
+            }
             if let Some(headers) = self.headers()? {
-                let mut request = request;
                 let headers_mut = request.headers_mut();
                 if let Some(headers_mut) = headers_mut {
                     headers_mut.extend(headers);
@@ -66,14 +99,18 @@ macro_rules! impl_query {
             client: &C,
             request: $crate::RequestBuilder,
         ) -> Result<$crate::Response<$crate::Bytes>, $crate::APIError<C::Error>> {
-            if let Some((mime, body)) = self.body()? {
-                client.rest(
+            match self.request_body()? {
+                Some((mime, $crate::RequestBody::Bytes(body))) => client.rest(
                     request
                         .header(::http::header::CONTENT_TYPE, mime.as_ref())
                         .body(body)?,
-                )
-            } else {
-                client.rest(request.body(Vec::new())?)
+                ),
+                Some((mime, body)) => client.rest_body(
+                    request
+                        .header(::http::header::CONTENT_TYPE, mime.as_ref())
+                        .body(body)?,
+                ),
+                None => client.rest(request.body(Vec::new())?),
             }
         }
     };
@@ -83,7 +120,7 @@ macro_rules! impl_query {
             response: $crate::Response<$crate::Bytes>,
         ) -> Result<T, $crate::APIError<C::Error>> {
             if !response.status().is_success() && !self.ignore_errors() {
-                Err($crate::APIErrorKind::Response(response))?
+                Err($crate::APIErrorKind::from_response(response))?
             } else {
                 Ok(self.deserialize(response)?)
             }
@@ -91,14 +128,7 @@ macro_rules! impl_query {
     };
     ("query") => {
         fn query(&self, client: &C) -> Result<T, $crate::APIError<C::Error>> {
-            $crate::Query::<T, C>::finalise(
-                self,
-                $crate::Query::<T, C>::send(
-                    self,
-                    client,
-                    $crate::Query::<T, C>::request(self, client)?,
-                )?,
-            )
+            $crate::macros::in_context!(sync $crate::Query<T, C>, self, client)
         }
     };
 }
@@ -115,12 +145,12 @@ macro_rules! impl_query_async {
             client: &C,
         ) -> Result<$crate::RequestBuilder, $crate::APIError<C::Error>> {
             let method = self.method();
-            let url = client.rest_endpoint(&self.url())?;
-            let request = ::http::Request::builder()
-                .method(method)
-                .uri(url.to_string());
+            let uri = client.rest_endpoint(&self.target())?;
+            let mut request = ::http::Request::builder().method(method).uri(uri);
+            if let Some(timeout) = self.timeout() {
+                request = request.extension($crate::RequestTimeout(timeout));
+            }
             if let Some(headers) = self.headers()? {
-                let mut request = request;
                 let headers_mut = request.headers_mut();
                 if let Some(headers_mut) = headers_mut {
                     headers_mut.extend(headers);
@@ -142,19 +172,32 @@ macro_rules! impl_query_async {
             client: &C,
             request: $crate::RequestBuilder,
         ) -> Result<$crate::Response<$crate::Bytes>, $crate::APIError<C::Error>> {
-            if let Some((mime, body)) = self.body()? {
-                client
-                    .rest_async(
-                        request
-                            .header(
-                                ::http::header::CONTENT_TYPE,
-                                ::http::header::HeaderValue::from_str(&mime)?,
-                            )
-                            .body(body)?,
-                    )
-                    .await
-            } else {
-                client.rest_async(request.body(Vec::new())?).await
+            match self.request_body()? {
+                Some((mime, $crate::RequestBody::Bytes(body))) => {
+                    client
+                        .rest_async(
+                            request
+                                .header(
+                                    ::http::header::CONTENT_TYPE,
+                                    ::http::header::HeaderValue::from_str(&mime)?,
+                                )
+                                .body(body)?,
+                        )
+                        .await
+                }
+                Some((mime, body)) => {
+                    client
+                        .rest_body_async(
+                            request
+                                .header(
+                                    ::http::header::CONTENT_TYPE,
+                                    ::http::header::HeaderValue::from_str(&mime)?,
+                                )
+                                .body(body)?,
+                        )
+                        .await
+                }
+                None => client.rest_async(request.body(Vec::new())?).await,
             }
         }
     };
@@ -164,7 +207,7 @@ macro_rules! impl_query_async {
             response: $crate::Response<$crate::Bytes>,
         ) -> Result<T, $crate::APIError<C::Error>> {
             if !response.status().is_success() && !self.ignore_errors() {
-                Err($crate::APIErrorKind::Response(response))?
+                Err($crate::APIErrorKind::from_response(response))?
             } else {
                 Ok(self.deserialize(response)?)
             }
@@ -172,16 +215,7 @@ macro_rules! impl_query_async {
     };
     ("query") => {
         async fn query_async(&self, client: &C) -> Result<T, $crate::APIError<C::Error>> {
-            $crate::AsyncQuery::<T, C>::finalise_async(
-                self,
-                $crate::AsyncQuery::<T, C>::send_async(
-                    self,
-                    client,
-                    $crate::AsyncQuery::<T, C>::request_async(self, client).await?,
-                )
-                .await?,
-            )
-            .await
+            $crate::macros::in_context!(async $crate::AsyncQuery<T, C>, self, client)
         }
     };
 }
@@ -0,0 +1,419 @@
+//! Describe endpoints and collect them into an OpenAPI 3.1 document.
+//!
+//! Endpoints implement [`DescribeEndpoint`], usually with `#[derive(DescribeEndpoint)]`, and are registered with [`OpenApi::endpoint`].
+use std::borrow::Cow;
+
+use http::{Method, StatusCode};
+use schemars::{Schema, SchemaGenerator, generate::SchemaSettings};
+use serde_json::{Map, Value, json};
+
+/// Where a parameter is sent.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ParameterLocation {
+    /// Part of the path, e.g. `/pets/{id}`.
+    Path,
+    /// Part of the query string.
+    Query,
+    /// A header.
+    Header,
+    /// A cookie.
+    Cookie,
+}
+impl ParameterLocation {
+    /// The name of the location in OpenAPI.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Path => "path",
+            Self::Query => "query",
+            Self::Header => "header",
+            Self::Cookie => "cookie",
+        }
+    }
+}
+
+/// A parameter of an endpoint.
+#[derive(Clone, Debug)]
+pub struct ParameterDescription {
+    /// The name, as sent.
+    pub name: Cow<'static, str>,
+    /// Where the parameter is sent.
+    pub location: ParameterLocation,
+    /// Whether the parameter must be sent.
+    pub required: bool,
+    /// A description of the parameter.
+    pub description: Option<Cow<'static, str>>,
+    /// The schema of the value.
+    pub schema: Schema,
+}
+
+/// A body, or a response body.
+#[derive(Clone, Debug)]
+pub struct MediaDescription {
+    /// The content type, e.g. `application/json`.
+    pub content_type: Cow<'static, str>,
+    /// Whether the body must be sent.
+    pub required: bool,
+    /// The schema of the body.
+    pub schema: Schema,
+}
+impl MediaDescription {
+    /// The OpenAPI content object.
+    fn content(&self) -> Value {
+        let mut content = Map::new();
+        content.insert(
+            self.content_type.to_string(),
+            json!({ "schema": self.schema }),
+        );
+        Value::Object(content)
+    }
+}
+
+/// Everything about an endpoint needed to describe it in OpenAPI.
+#[derive(Clone, Debug)]
+pub struct EndpointDescription {
+    /// The method for the endpoint.
+    pub method: Method,
+    /// The path template, e.g. `/pets/{id}`.
+    pub path: Cow<'static, str>,
+    /// A unique name for the endpoint.
+    pub operation_id: Option<Cow<'static, str>>,
+    /// A short summary of the endpoint.
+    pub summary: Option<Cow<'static, str>>,
+    /// A longer description of the endpoint.
+    pub description: Option<Cow<'static, str>>,
+    /// Whether the endpoint is deprecated.
+    pub deprecated: bool,
+    /// The path, query, header and cookie parameters.
+    pub parameters: Vec<ParameterDescription>,
+    /// The request body.
+    pub body: Option<MediaDescription>,
+    /// The successful response.
+    pub response: Option<MediaDescription>,
+    /// The status of the successful response.
+    pub status: StatusCode,
+}
+impl EndpointDescription {
+    /// The OpenAPI operation object.
+    fn operation(&self) -> Value {
+        let mut operation = Map::new();
+        if let Some(operation_id) = &self.operation_id {
+            operation.insert("operationId".into(), json!(operation_id));
+        }
+        if let Some(summary) = &self.summary {
+            operation.insert("summary".into(), json!(summary));
+        }
+        if let Some(description) = &self.description {
+            operation.insert("description".into(), json!(description));
+        }
+        if self.deprecated {
+            operation.insert("deprecated".into(), json!(true));
+        }
+
+        if !self.parameters.is_empty() {
+            let parameters = self
+                .parameters
+                .iter()
+                .map(|parameter| {
+                    let mut object = Map::new();
+                    object.insert("name".into(), json!(parameter.name));
+                    object.insert("in".into(), json!(parameter.location.as_str()));
+                    object.insert("required".into(), json!(parameter.required));
+                    if let Some(description) = &parameter.description {
+                        object.insert("description".into(), json!(description));
+                    }
+                    object.insert("schema".into(), json!(parameter.schema));
+                    Value::Object(object)
+                })
+                .collect();
+            operation.insert("parameters".into(), Value::Array(parameters));
+        }
+
+        if let Some(body) = &self.body {
+            operation.insert(
+                "requestBody".into(),
+                json!({ "required": body.required, "content": body.content() }),
+            );
+        }
+
+        let response = match &self.response {
+            Some(response) => json!({
+                "description": "Successful response",
+                "content": response.content(),
+            }),
+            None => json!({ "description": "Successful response" }),
+        };
+        let mut responses = Map::new();
+        responses.insert(self.status.as_str().into(), response);
+        operation.insert("responses".into(), Value::Object(responses));
+
+        Value::Object(operation)
+    }
+}
+
+/// A trait for describing an endpoint in OpenAPI.
+///
+/// Usually implemented with `#[derive(DescribeEndpoint)]`.
+pub trait DescribeEndpoint {
+    /// Describe the endpoint, adding any named schemas to `generator`.
+    fn describe(generator: &mut SchemaGenerator) -> EndpointDescription;
+}
+
+/// Collects endpoints into an OpenAPI 3.1 document.
+///
+/// Schemas of named types are placed in `#/components/schemas`.
+pub struct OpenApi {
+    info: Map<String, Value>,
+    servers: Vec<Value>,
+    paths: Map<String, Value>,
+    generator: SchemaGenerator,
+}
+impl OpenApi {
+    /// Start a document for an API.
+    pub fn new(title: impl Into<String>, version: impl Into<String>) -> Self {
+        let mut info = Map::new();
+        info.insert("title".into(), Value::String(title.into()));
+        info.insert("version".into(), Value::String(version.into()));
+
+        let settings = SchemaSettings::draft2020_12().with(|settings| {
+            settings.definitions_path = "/components/schemas".into();
+        });
+        Self {
+            info,
+            servers: Vec::new(),
+            paths: Map::new(),
+            generator: settings.into_generator(),
+        }
+    }
+
+    /// A description of the API.
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.info
+            .insert("description".into(), Value::String(description.into()));
+        self
+    }
+
+    /// Add a server which hosts the API.
+    pub fn server(mut self, url: impl Into<String>) -> Self {
+        self.servers.push(json!({ "url": url.into() }));
+        self
+    }
+
+    /// Add an endpoint.
+    pub fn endpoint<E: DescribeEndpoint>(mut self) -> Self {
+        let description = E::describe(&mut self.generator);
+        self.add(description);
+        self
+    }
+
+    /// Add an endpoint which has already been described.
+    ///
+    /// Any schemas it refers to must have been added to [`OpenApi::generator`].
+    pub fn add(&mut self, description: EndpointDescription) {
+        let method = description.method.as_str().to_ascii_lowercase();
+        let item = self
+            .paths
+            .entry(description.path.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Value::Object(item) = item {
+            item.insert(method, description.operation());
+        }
+    }
+
+    /// The schema generator, for describing endpoints by hand.
+    pub fn generator(&mut self) -> &mut SchemaGenerator {
+        &mut self.generator
+    }
+
+    /// Build the document.
+    pub fn build(mut self) -> Value {
+        let mut document = Map::new();
+        document.insert("openapi".into(), json!("3.1.0"));
+        document.insert("info".into(), Value::Object(self.info));
+        if !self.servers.is_empty() {
+            document.insert("servers".into(), Value::Array(self.servers));
+        }
+        document.insert("paths".into(), Value::Object(self.paths));
+
+        let schemas = self.generator.take_definitions(true);
+        if !schemas.is_empty() {
+            document.insert("components".into(), json!({ "schemas": schemas }));
+        }
+        Value::Object(document)
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use crate::{DescribeEndpoint, Endpoint, api_endpoint};
+
+    use super::*;
+
+    /// A pet.
+    #[derive(serde::Serialize, schemars::JsonSchema)]
+    struct Pet {
+        id: u64,
+        name: String,
+    }
+
+    /// Get a pet.
+    ///
+    /// Returns a single pet
+    /// by its ID.
+    #[allow(dead_code)]
+    #[derive(DescribeEndpoint)]
+    #[describe(response = Pet)]
+    struct GetPet {
+        /// The ID of the pet.
+        #[describe(path)]
+        id: u64,
+        /// Include the owner.
+        #[describe(query, rename = "withOwner")]
+        with_owner: Option<bool>,
+        #[describe(header, rename = "X-Request-Id")]
+        request_id: String,
+    }
+    #[api_endpoint(method = GET, path = "format!(\"pets/{}\", self.id)")]
+    impl Endpoint for GetPet {}
+
+    /// Create a pet.
+    #[derive(serde::Serialize, schemars::JsonSchema, DescribeEndpoint)]
+    #[describe(self_as_body = "application/json", response = Pet, status = 201)]
+    struct CreatePet {
+        name: String,
+    }
+    #[api_endpoint(method = POST, path = "\"pets\"", self_as_body = "application/json")]
+    impl Endpoint for CreatePet {}
+
+    /// Replace a pet.
+    #[allow(dead_code)]
+    #[derive(DescribeEndpoint)]
+    #[describe(method = PUT, path = "/pets/{id}", operation_id = "replacePet")]
+    struct UpdatePet {
+        #[describe(path)]
+        id: u64,
+        #[describe(body = "application/merge-patch+json")]
+        pet: Option<Pet>,
+    }
+
+    fn document() -> Value {
+        OpenApi::new("Pet Store", "1.0.0")
+            .description("Pets for sale")
+            .server("https://example.com/v1")
+            .endpoint::<GetPet>()
+            .endpoint::<CreatePet>()
+            .endpoint::<UpdatePet>()
+            .build()
+    }
+
+    #[test]
+    fn describes_endpoints() {
+        let document = document();
+        assert_eq!(document["openapi"], "3.1.0");
+        assert_eq!(document["info"]["title"], "Pet Store");
+        assert_eq!(document["info"]["description"], "Pets for sale");
+        assert_eq!(document["servers"][0]["url"], "https://example.com/v1");
+
+        let get = &document["paths"]["/pets/{id}"]["get"];
+        assert_eq!(get["operationId"], "GetPet");
+        assert_eq!(get["summary"], "Get a pet.");
+        assert_eq!(get["description"], "Returns a single pet\nby its ID.");
+        assert_eq!(
+            get["parameters"],
+            json!([
+                {
+                    "name": "id",
+                    "in": "path",
+                    "required": true,
+                    "description": "The ID of the pet.",
+                    "schema": { "type": "integer", "format": "uint64", "minimum": 0 },
+                },
+                {
+                    "name": "withOwner",
+                    "in": "query",
+                    "required": false,
+                    "description": "Include the owner.",
+                    "schema": { "type": "boolean" },
+                },
+                {
+                    "name": "X-Request-Id",
+                    "in": "header",
+                    "required": true,
+                    "schema": { "type": "string" },
+                },
+            ])
+        );
+        assert!(get.get("requestBody").is_none());
+        assert_eq!(
+            get["responses"]["200"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/Pet"
+        );
+    }
+
+    #[test]
+    fn describes_bodies_and_statuses() {
+        let document = document();
+
+        let create = &document["paths"]["/pets"]["post"];
+        assert_eq!(create["requestBody"]["required"], true);
+        assert_eq!(
+            create["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/CreatePet"
+        );
+        assert!(create["responses"].get("200").is_none());
+        assert_eq!(
+            create["responses"]["201"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/Pet"
+        );
+
+        // Endpoints sharing a path are kept side by side
+        let update = &document["paths"]["/pets/{id}"]["put"];
+        assert_eq!(update["operationId"], "replacePet");
+        assert_eq!(update["requestBody"]["required"], false);
+        assert_eq!(
+            update["requestBody"]["content"]["application/merge-patch+json"]["schema"]["$ref"],
+            "#/components/schemas/Pet"
+        );
+        assert_eq!(
+            update["responses"]["200"],
+            json!({ "description": "Successful response" })
+        );
+        assert!(document["paths"]["/pets/{id}"]["get"].is_object());
+
+        let schemas = document["components"]["schemas"].as_object().unwrap();
+        let mut names: Vec<_> = schemas.keys().collect();
+        names.sort();
+        assert_eq!(names, ["CreatePet", "Pet"]);
+        assert_eq!(schemas["Pet"]["required"], json!(["id", "name"]));
+    }
+
+    #[test]
+    fn describes_endpoints_by_hand() {
+        let mut openapi = OpenApi::new("Pet Store", "1.0.0");
+        let schema = openapi.generator().subschema_for::<Pet>();
+        openapi.add(EndpointDescription {
+            method: Method::DELETE,
+            path: "/pets/{id}".into(),
+            operation_id: None,
+            summary: None,
+            description: None,
+            deprecated: true,
+            parameters: Vec::new(),
+            body: None,
+            response: Some(MediaDescription {
+                content_type: "application/json".into(),
+                required: true,
+                schema,
+            }),
+            status: StatusCode::ACCEPTED,
+        });
+
+        let document = openapi.build();
+        let delete = &document["paths"]["/pets/{id}"]["delete"];
+        assert_eq!(delete["deprecated"], true);
+        assert!(delete.get("operationId").is_none());
+        assert!(delete.get("parameters").is_none());
+        assert!(delete["responses"]["202"].is_object());
+        assert!(document["components"]["schemas"]["Pet"].is_object());
+        assert!(document.get("servers").is_none());
+    }
+}
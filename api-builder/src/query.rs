@@ -1,6 +1,6 @@
 use core::future::Future;
 
-use crate::{APIError, AsyncClient, Client};
+use crate::{APIError, AsyncClient, Client, StreamingClient};
 use bytes::Bytes;
 use http::{Response, request::Builder};
 
@@ -68,3 +68,86 @@ where
     where
         C::Error: core::error::Error + Sync + Send + 'static;
 }
+
+/// A trait which represents a query whose response body is streamed from a client.
+///
+/// NOTE: make sure to respect [Endpoint::ignore_errors](crate::Endpoint::ignore_errors) within [StreamQuery::finalise].
+pub trait StreamQuery<C>
+where
+    C: StreamingClient,
+{
+    /// Starts building the query request.
+    fn request(&self, client: &C) -> Result<Builder, APIError<C::Error>>;
+
+    /// Sends the request.
+    fn send(&self, client: &C, request: Builder)
+    -> Result<Response<C::Body>, APIError<C::Error>>;
+
+    /// Finalises the request by returning the response.
+    fn finalise(
+        &self,
+        response: Response<C::Body>,
+    ) -> Result<Response<C::Body>, APIError<C::Error>>;
+
+    /// Perform the query against the client.
+    fn query(&self, client: &C) -> Result<Response<C::Body>, APIError<C::Error>>;
+}
+
+/// A trait which represents an asynchronous query whose response body is streamed from a client.
+///
+/// NOTE: make sure to respect [Endpoint::ignore_errors](crate::Endpoint::ignore_errors) within [AsyncStreamQuery::finalise_async].
+#[cfg(feature = "stream")]
+pub trait AsyncStreamQuery<C>
+where
+    C: crate::AsyncStreamingClient,
+{
+    /// Starts building the query request.
+    fn request_async(
+        &self,
+        client: &C,
+    ) -> impl Future<Output = Result<Builder, APIError<C::Error>>> + Send;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    /// Sends the request.
+    fn send_async(
+        &self,
+        client: &C,
+        request: Builder,
+    ) -> impl Future<Output = Result<Response<C::Stream>, APIError<C::Error>>> + Send;
+    #[cfg(target_arch = "wasm32")]
+    /// Sends the request.
+    fn send_async(
+        &self,
+        client: &C,
+        request: Builder,
+    ) -> impl Future<Output = Result<Response<C::Stream>, APIError<C::Error>>>;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    /// Finalises the request by returning the response.
+    fn finalise_async(
+        &self,
+        response: Response<C::Stream>,
+    ) -> impl Future<Output = Result<Response<C::Stream>, APIError<C::Error>>> + Send;
+    #[cfg(target_arch = "wasm32")]
+    /// Finalises the request by returning the response.
+    fn finalise_async(
+        &self,
+        response: Response<C::Stream>,
+    ) -> impl Future<Output = Result<Response<C::Stream>, APIError<C::Error>>>;
+
+    /// Perform the query asynchronously against the client.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn query_async(
+        &self,
+        client: &C,
+    ) -> impl Future<Output = Result<Response<C::Stream>, APIError<C::Error>>> + Send
+    where
+        C::Error: core::error::Error + Sync + Send + 'static;
+    #[cfg(target_arch = "wasm32")]
+    fn query_async(
+        &self,
+        client: &C,
+    ) -> impl Future<Output = Result<Response<C::Stream>, APIError<C::Error>>>
+    where
+        C::Error: core::error::Error + Sync + Send + 'static;
+}
@@ -1,16 +1,18 @@
 use core::ops::{Deref, DerefMut};
 use std::borrow::Cow;
 
+use crate::IntoQueryValue;
+
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
-pub struct QueryParamPair {
-    pub key: Cow<'static, str>,
-    pub value: Cow<'static, str>,
+pub struct QueryParamPair<'a> {
+    pub key: Cow<'a, str>,
+    pub value: Cow<'a, str>,
 }
-impl QueryParamPair {
+impl<'a> QueryParamPair<'a> {
     pub fn new<K, V>(key: K, value: V) -> Self
     where
-        K: Into<Cow<'static, str>>,
-        V: Into<Cow<'static, str>>,
+        K: Into<Cow<'a, str>>,
+        V: Into<Cow<'a, str>>,
     {
         Self {
             key: key.into(),
@@ -18,7 +20,7 @@ impl QueryParamPair {
         }
     }
 }
-impl<K: Into<Cow<'static, str>>, V: Into<Cow<'static, str>>> From<(K, V)> for QueryParamPair {
+impl<'a, K: Into<Cow<'a, str>>, V: Into<Cow<'a, str>>> From<(K, V)> for QueryParamPair<'a> {
     fn from(pair: (K, V)) -> Self {
         Self {
             key: pair.0.into(),
@@ -27,63 +29,69 @@ impl<K: Into<Cow<'static, str>>, V: Into<Cow<'static, str>>> From<(K, V)> for Qu
     }
 }
 
+/// The query parameters of an endpoint.
+///
+/// Keys and values may borrow from the endpoint for `'a`, so they don't need to be cloned.
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
-pub struct QueryParamPairs(pub Vec<QueryParamPair>);
-impl QueryParamPairs {
-    pub fn append<T: Into<QueryParamPairs>>(&mut self, other: T) {
+pub struct QueryParamPairs<'a>(pub Vec<QueryParamPair<'a>>);
+impl<'a> QueryParamPairs<'a> {
+    pub fn append<T: Into<QueryParamPairs<'a>>>(&mut self, other: T) {
         self.0.append(&mut other.into());
     }
 
-    pub fn push<T: Into<QueryParamPair>>(&mut self, value: T) {
-        self.0.push(value.into());
+    /// Add a parameter, unless the value is `None`.
+    ///
+    /// References to a [`ToQueryValue`](crate::ToQueryValue) are borrowed, e.g. `params.push(("name", &self.name))`.
+    pub fn push<K: Into<Cow<'a, str>>, V: IntoQueryValue<'a>>(&mut self, (key, value): (K, V)) {
+        if let Some(value) = value.into_query_value() {
+            self.0.push(QueryParamPair::new(key, value));
+        }
     }
 
-    pub fn push_hashmap<K: Into<Cow<'static, str>>, V: Into<Cow<'static, str>>>(
+    pub fn push_hashmap<K: Into<Cow<'a, str>>, V: IntoQueryValue<'a>>(
         &mut self,
         name: &str,
         value: std::collections::HashMap<K, V>,
     ) {
-        let mut entries: Vec<(Cow<'static, str>, Cow<'static, str>)> = value
-            .into_iter()
-            .map(|(k, v)| (k.into(), v.into()))
-            .collect();
+        let mut entries: Vec<(Cow<'a, str>, V)> =
+            value.into_iter().map(|(k, v)| (k.into(), v)).collect();
         entries.sort_by(|a, b| a.0.cmp(&b.0));
         for (key, value) in entries {
             self.push((format!("{}[{}]", name, key), value));
         }
     }
 }
-impl Deref for QueryParamPairs {
-    type Target = Vec<QueryParamPair>;
+impl<'a> Deref for QueryParamPairs<'a> {
+    type Target = Vec<QueryParamPair<'a>>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
-impl DerefMut for QueryParamPairs {
+impl DerefMut for QueryParamPairs<'_> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.0
     }
 }
-impl From<Vec<QueryParamPair>> for QueryParamPairs {
-    fn from(pairs: Vec<QueryParamPair>) -> Self {
+impl<'a> From<Vec<QueryParamPair<'a>>> for QueryParamPairs<'a> {
+    fn from(pairs: Vec<QueryParamPair<'a>>) -> Self {
         Self(pairs)
     }
 }
-impl<K: Into<Cow<'static, str>>, V: Into<Cow<'static, str>>> From<Vec<(K, V)>> for QueryParamPairs {
+impl<'a, K: Into<Cow<'a, str>>, V: Into<Cow<'a, str>>> From<Vec<(K, V)>> for QueryParamPairs<'a> {
     fn from(value: Vec<(K, V)>) -> Self {
         Self(value.into_iter().map(|x| x.into()).collect())
     }
 }
 
-impl Extend<QueryParamPair> for QueryParamPairs {
-    fn extend<T: IntoIterator<Item = QueryParamPair>>(&mut self, iter: T) {
+impl<'a> Extend<QueryParamPair<'a>> for QueryParamPairs<'a> {
+    fn extend<T: IntoIterator<Item = QueryParamPair<'a>>>(&mut self, iter: T) {
         self.0.extend(iter);
     }
 }
 
-impl FromIterator<QueryParamPair> for QueryParamPairs {
-    fn from_iter<T: IntoIterator<Item = QueryParamPair>>(iter: T) -> Self {
+impl<'a> FromIterator<QueryParamPair<'a>> for QueryParamPairs<'a> {
+    fn from_iter<T: IntoIterator<Item = QueryParamPair<'a>>>(iter: T) -> Self {
         Self(iter.into_iter().collect())
     }
 }
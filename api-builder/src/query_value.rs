@@ -0,0 +1,293 @@
+use std::borrow::Cow;
+
+/// A trait for turning a value into a query parameter value.
+///
+/// The value may borrow from `self`, so strings aren't cloned.
+/// Enums can use `#[derive(ToQueryValue)]`, which takes `#[query_value(rename_all = "...")]` and `#[query_value(rename = "...")]` like `serde`.
+pub trait ToQueryValue {
+    /// The value, or `None` to leave the parameter out.
+    fn to_query_value(&self) -> Option<Cow<'_, str>>;
+}
+
+/// A value which can be given to [`QueryParamPairs::push`](crate::QueryParamPairs::push), borrowing for `'a`.
+///
+/// Implemented for references to any [`ToQueryValue`], and for owned values which implement it.
+pub trait IntoQueryValue<'a> {
+    /// The value, or `None` to leave the parameter out.
+    fn into_query_value(self) -> Option<Cow<'a, str>>;
+}
+
+impl<T> ToQueryValue for &T
+where
+    T: ToQueryValue + ?Sized,
+{
+    fn to_query_value(&self) -> Option<Cow<'_, str>> {
+        (**self).to_query_value()
+    }
+}
+impl<'a, T> IntoQueryValue<'a> for &'a T
+where
+    T: ToQueryValue + ?Sized,
+{
+    fn into_query_value(self) -> Option<Cow<'a, str>> {
+        self.to_query_value()
+    }
+}
+
+impl<T: ToQueryValue> ToQueryValue for Option<T> {
+    fn to_query_value(&self) -> Option<Cow<'_, str>> {
+        self.as_ref().and_then(ToQueryValue::to_query_value)
+    }
+}
+impl<'a, T: IntoQueryValue<'a>> IntoQueryValue<'a> for Option<T> {
+    fn into_query_value(self) -> Option<Cow<'a, str>> {
+        self.and_then(IntoQueryValue::into_query_value)
+    }
+}
+
+impl ToQueryValue for str {
+    fn to_query_value(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self))
+    }
+}
+
+impl ToQueryValue for String {
+    fn to_query_value(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self))
+    }
+}
+impl<'a> IntoQueryValue<'a> for String {
+    fn into_query_value(self) -> Option<Cow<'a, str>> {
+        Some(Cow::Owned(self))
+    }
+}
+
+impl ToQueryValue for Cow<'_, str> {
+    fn to_query_value(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self))
+    }
+}
+impl<'a> IntoQueryValue<'a> for Cow<'a, str> {
+    fn into_query_value(self) -> Option<Cow<'a, str>> {
+        Some(self)
+    }
+}
+
+impl ToQueryValue for bool {
+    fn to_query_value(&self) -> Option<Cow<'_, str>> {
+        (*self).into_query_value()
+    }
+}
+impl<'a> IntoQueryValue<'a> for bool {
+    fn into_query_value(self) -> Option<Cow<'a, str>> {
+        Some(Cow::Borrowed(if self { "true" } else { "false" }))
+    }
+}
+
+/// Implements [`IntoQueryValue`] for owned values, whose [`ToQueryValue`] is always owned.
+macro_rules! impl_into_owned {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl<'a> IntoQueryValue<'a> for $ty {
+                fn into_query_value(self) -> Option<Cow<'a, str>> {
+                    self.to_query_value().map(|x| Cow::Owned(x.into_owned()))
+                }
+            }
+        )*
+    };
+}
+
+/// Implements [`ToQueryValue`] and [`IntoQueryValue`] using [`ToString`].
+macro_rules! impl_to_string {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl ToQueryValue for $ty {
+                fn to_query_value(&self) -> Option<Cow<'_, str>> {
+                    Some(Cow::Owned(self.to_string()))
+                }
+            }
+            impl_into_owned!($ty);
+        )*
+    };
+}
+impl_to_string!(
+    char, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64,
+);
+
+impl ToQueryValue for serde_json::Value {
+    fn to_query_value(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Self::Null => None,
+            Self::String(x) => Some(Cow::Borrowed(x)),
+            x => Some(Cow::Owned(x.to_string())),
+        }
+    }
+}
+impl<'a> IntoQueryValue<'a> for serde_json::Value {
+    fn into_query_value(self) -> Option<Cow<'a, str>> {
+        match self {
+            Self::String(x) => Some(Cow::Owned(x)),
+            x => x.to_query_value().map(|x| Cow::Owned(x.into_owned())),
+        }
+    }
+}
+
+/// A timestamp sent as whole seconds since the Unix epoch.
+#[cfg(any(feature = "chrono", feature = "time"))]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Unix<T>(pub T);
+
+/// A timestamp sent as milliseconds since the Unix epoch.
+#[cfg(any(feature = "chrono", feature = "time"))]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct UnixMillis<T>(pub T);
+
+/// Dates are sent as `YYYY-MM-DD`, and date-times as RFC 3339.
+#[cfg(feature = "chrono")]
+mod chrono_impls {
+    use std::borrow::Cow;
+
+    use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone};
+
+    use super::{IntoQueryValue, ToQueryValue, Unix, UnixMillis};
+
+    impl<Tz> ToQueryValue for DateTime<Tz>
+    where
+        Tz: TimeZone,
+        Tz::Offset: core::fmt::Display,
+    {
+        fn to_query_value(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Owned(
+                self.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            ))
+        }
+    }
+    impl<'a, Tz> IntoQueryValue<'a> for DateTime<Tz>
+    where
+        Tz: TimeZone,
+        Tz::Offset: core::fmt::Display,
+    {
+        fn into_query_value(self) -> Option<Cow<'a, str>> {
+            Some(Cow::Owned(
+                self.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            ))
+        }
+    }
+
+    impl ToQueryValue for NaiveDate {
+        fn to_query_value(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Owned(self.format("%Y-%m-%d").to_string()))
+        }
+    }
+
+    impl ToQueryValue for NaiveDateTime {
+        fn to_query_value(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Owned(self.format("%Y-%m-%dT%H:%M:%S%.f").to_string()))
+        }
+    }
+
+    impl_into_owned!(NaiveDate, NaiveDateTime);
+
+    impl<Tz: TimeZone> ToQueryValue for Unix<DateTime<Tz>> {
+        fn to_query_value(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Owned(self.0.timestamp().to_string()))
+        }
+    }
+    impl<'a, Tz: TimeZone> IntoQueryValue<'a> for Unix<DateTime<Tz>> {
+        fn into_query_value(self) -> Option<Cow<'a, str>> {
+            Some(Cow::Owned(self.0.timestamp().to_string()))
+        }
+    }
+
+    impl<Tz: TimeZone> ToQueryValue for UnixMillis<DateTime<Tz>> {
+        fn to_query_value(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Owned(self.0.timestamp_millis().to_string()))
+        }
+    }
+    impl<'a, Tz: TimeZone> IntoQueryValue<'a> for UnixMillis<DateTime<Tz>> {
+        fn into_query_value(self) -> Option<Cow<'a, str>> {
+            Some(Cow::Owned(self.0.timestamp_millis().to_string()))
+        }
+    }
+}
+
+/// Dates are sent as `YYYY-MM-DD`, and date-times as RFC 3339 where possible.
+#[cfg(feature = "time")]
+mod time_impls {
+    use std::borrow::Cow;
+
+    use time::{Date, OffsetDateTime, UtcOffset, format_description::well_known::Rfc3339};
+
+    use super::{IntoQueryValue, ToQueryValue, Unix, UnixMillis};
+
+    impl ToQueryValue for OffsetDateTime {
+        fn to_query_value(&self) -> Option<Cow<'_, str>> {
+            // RFC 3339 can't hold offsets with seconds, so those are sent in UTC
+            self.format(&Rfc3339)
+                .or_else(|_| self.to_offset(UtcOffset::UTC).format(&Rfc3339))
+                .ok()
+                .map(Cow::Owned)
+        }
+    }
+
+    impl ToQueryValue for Date {
+        fn to_query_value(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Owned(self.to_string()))
+        }
+    }
+
+    impl ToQueryValue for Unix<OffsetDateTime> {
+        fn to_query_value(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Owned(self.0.unix_timestamp().to_string()))
+        }
+    }
+
+    impl ToQueryValue for UnixMillis<OffsetDateTime> {
+        fn to_query_value(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Owned(
+                (self.0.unix_timestamp_nanos() / 1_000_000).to_string(),
+            ))
+        }
+    }
+
+    impl_into_owned!(
+        OffsetDateTime,
+        Date,
+        Unix<OffsetDateTime>,
+        UnixMillis<OffsetDateTime>
+    );
+
+    #[cfg(test)]
+    mod tests {
+        use time::{Month, Time};
+
+        use super::*;
+
+        #[test]
+        fn sends_second_offsets_in_utc() {
+            let date = Date::from_calendar_date(2024, Month::March, 1).unwrap();
+            let date_time = date.with_time(Time::MIDNIGHT);
+
+            let utc = date_time.assume_offset(UtcOffset::UTC);
+            assert_eq!(
+                utc.to_query_value().as_deref(),
+                Some("2024-03-01T00:00:00Z")
+            );
+
+            let offset = date_time.assume_offset(UtcOffset::from_hms(1, 0, 30).unwrap());
+            assert_eq!(
+                offset.to_query_value().as_deref(),
+                Some("2024-02-29T22:59:30Z")
+            );
+        }
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl ToQueryValue for uuid::Uuid {
+    fn to_query_value(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Owned(self.hyphenated().to_string()))
+    }
+}
+#[cfg(feature = "uuid")]
+impl_into_owned!(uuid::Uuid);
@@ -0,0 +1,387 @@
+use core::fmt;
+use std::{
+    borrow::Cow,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::BodyError;
+
+/// A boxed stream of request body chunks.
+#[cfg(feature = "stream")]
+#[cfg(not(target_arch = "wasm32"))]
+pub type BodyStream = core::pin::Pin<
+    Box<dyn futures_core::Stream<Item = Result<bytes::Bytes, std::io::Error>> + Send + 'static>,
+>;
+/// A boxed stream of request body chunks.
+#[cfg(feature = "stream")]
+#[cfg(target_arch = "wasm32")]
+pub type BodyStream = core::pin::Pin<
+    Box<dyn futures_core::Stream<Item = Result<bytes::Bytes, std::io::Error>> + 'static>,
+>;
+
+/// A boxed asynchronous reader for request bodies.
+#[cfg(feature = "stream")]
+#[cfg(not(target_arch = "wasm32"))]
+pub type BodyAsyncRead = core::pin::Pin<Box<dyn futures_util::io::AsyncRead + Send + 'static>>;
+/// A boxed asynchronous reader for request bodies.
+#[cfg(feature = "stream")]
+#[cfg(target_arch = "wasm32")]
+pub type BodyAsyncRead = core::pin::Pin<Box<dyn futures_util::io::AsyncRead + 'static>>;
+
+/// The body of a request.
+///
+/// [`RequestBody::Bytes`] is the cheap default, the other variants allow the body to be sent without holding it in memory.
+/// Readers and streams are sent with `Content-Length` when `length` is known, otherwise they are chunked.
+pub enum RequestBody {
+    /// An in-memory body.
+    Bytes(Vec<u8>),
+    /// The contents of a file.
+    ///
+    /// Sent asynchronously, it is read on a blocking thread with the `tokio` feature, otherwise on the calling task.
+    File(PathBuf),
+    /// A blocking reader.
+    ///
+    /// Sent asynchronously, it is read on a blocking thread with the `tokio` feature, otherwise on the calling task.
+    Reader {
+        reader: Box<dyn Read + Send + 'static>,
+        length: Option<u64>,
+    },
+    /// An asynchronous reader.
+    #[cfg(feature = "stream")]
+    AsyncReader {
+        reader: BodyAsyncRead,
+        length: Option<u64>,
+    },
+    /// A stream of chunks.
+    #[cfg(feature = "stream")]
+    Stream {
+        stream: BodyStream,
+        length: Option<u64>,
+    },
+}
+impl RequestBody {
+    /// Creates a body from the contents of a file.
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Self::File(path.into())
+    }
+
+    /// Creates a chunked body from a blocking reader.
+    pub fn reader(reader: impl Read + Send + 'static) -> Self {
+        Self::Reader {
+            reader: Box::new(reader),
+            length: None,
+        }
+    }
+
+    /// Creates a body with a known length from a blocking reader.
+    pub fn sized_reader(reader: impl Read + Send + 'static, length: u64) -> Self {
+        Self::Reader {
+            reader: Box::new(reader),
+            length: Some(length),
+        }
+    }
+
+    /// Creates a chunked body from an asynchronous reader.
+    #[cfg(feature = "stream")]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn async_reader(reader: impl futures_util::io::AsyncRead + Send + 'static) -> Self {
+        Self::AsyncReader {
+            reader: Box::pin(reader),
+            length: None,
+        }
+    }
+
+    /// Creates a body with a known length from an asynchronous reader.
+    #[cfg(feature = "stream")]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn sized_async_reader(
+        reader: impl futures_util::io::AsyncRead + Send + 'static,
+        length: u64,
+    ) -> Self {
+        Self::AsyncReader {
+            reader: Box::pin(reader),
+            length: Some(length),
+        }
+    }
+
+    /// Creates a chunked body from a stream.
+    #[cfg(feature = "stream")]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn stream<S>(stream: S) -> Self
+    where
+        S: futures_core::Stream<Item = Result<bytes::Bytes, std::io::Error>> + Send + 'static,
+    {
+        Self::Stream {
+            stream: Box::pin(stream),
+            length: None,
+        }
+    }
+
+    /// Creates a body with a known length from a stream.
+    #[cfg(feature = "stream")]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn sized_stream<S>(stream: S, length: u64) -> Self
+    where
+        S: futures_core::Stream<Item = Result<bytes::Bytes, std::io::Error>> + Send + 'static,
+    {
+        Self::Stream {
+            stream: Box::pin(stream),
+            length: Some(length),
+        }
+    }
+
+    /// The length of the body, if known without reading it.
+    pub fn len(&self) -> Option<u64> {
+        match self {
+            Self::Bytes(bytes) => Some(bytes.len() as u64),
+            Self::File(path) => std::fs::metadata(path).ok().map(|x| x.len()),
+            Self::Reader { length, .. } => *length,
+            #[cfg(feature = "stream")]
+            Self::AsyncReader { length, .. } => *length,
+            #[cfg(feature = "stream")]
+            Self::Stream { length, .. } => *length,
+        }
+    }
+
+    /// Whether the body is known to be empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+
+    /// Reads the entire body into memory.
+    ///
+    /// Fails with [`BodyError::AsyncOnly`] for asynchronous readers and streams, use [`RequestBody::into_bytes_async`] instead.
+    pub fn into_bytes(self) -> Result<Vec<u8>, BodyError> {
+        match self {
+            Self::Bytes(bytes) => Ok(bytes),
+            Self::File(path) => Ok(std::fs::read(path)?),
+            Self::Reader { mut reader, length } => {
+                let mut buf = Vec::with_capacity(length.unwrap_or_default() as usize);
+                reader.read_to_end(&mut buf)?;
+                Ok(buf)
+            }
+            #[cfg(feature = "stream")]
+            Self::AsyncReader { .. } | Self::Stream { .. } => Err(BodyError::AsyncOnly),
+        }
+    }
+
+    /// Reads the entire body into memory asynchronously.
+    ///
+    /// Files and blocking readers are read with [`tokio::task::spawn_blocking`] with the `tokio` feature.
+    /// Otherwise they are read inline, blocking the calling task until they are done.
+    pub async fn into_bytes_async(self) -> Result<Vec<u8>, BodyError> {
+        match self {
+            #[cfg(feature = "stream")]
+            Self::AsyncReader { mut reader, length } => {
+                use futures_util::io::AsyncReadExt;
+
+                let mut buf = Vec::with_capacity(length.unwrap_or_default() as usize);
+                reader.read_to_end(&mut buf).await?;
+                Ok(buf)
+            }
+            #[cfg(feature = "stream")]
+            Self::Stream { mut stream, length } => {
+                use futures_util::StreamExt;
+
+                let mut buf = Vec::with_capacity(length.unwrap_or_default() as usize);
+                while let Some(chunk) = stream.next().await {
+                    buf.extend_from_slice(&chunk?);
+                }
+                Ok(buf)
+            }
+            Self::Bytes(bytes) => Ok(bytes),
+            #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+            body => tokio::task::spawn_blocking(move || body.into_bytes())
+                .await
+                .map_err(|e| BodyError::Io(e.into()))?,
+            #[cfg(not(all(feature = "tokio", not(target_arch = "wasm32"))))]
+            body => body.into_bytes(),
+        }
+    }
+
+    /// Converts the body into a stream of chunks.
+    ///
+    /// Files and blocking readers are read with [`tokio::task::spawn_blocking`], so need the `tokio` feature.
+    /// Otherwise they fail with [`BodyError::BlockingOnly`].
+    #[cfg(feature = "stream")]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn into_stream(self) -> Result<BodyStream, BodyError> {
+        match self {
+            Self::Bytes(bytes) => Ok(Box::pin(futures_util::stream::once(async move {
+                Ok(bytes::Bytes::from(bytes))
+            }))),
+            #[cfg(feature = "tokio")]
+            Self::File(path) => Ok(blocking_stream(BlockingSource::File(path))),
+            #[cfg(feature = "tokio")]
+            Self::Reader { reader, .. } => Ok(blocking_stream(BlockingSource::Reader(reader))),
+            #[cfg(not(feature = "tokio"))]
+            Self::File(_) | Self::Reader { .. } => Err(BodyError::BlockingOnly),
+            Self::AsyncReader { reader, .. } => Ok(reader_stream(reader)),
+            Self::Stream { stream, .. } => Ok(stream),
+        }
+    }
+}
+impl From<Vec<u8>> for RequestBody {
+    fn from(value: Vec<u8>) -> Self {
+        Self::Bytes(value)
+    }
+}
+impl From<bytes::Bytes> for RequestBody {
+    fn from(value: bytes::Bytes) -> Self {
+        Self::Bytes(value.into())
+    }
+}
+impl From<String> for RequestBody {
+    fn from(value: String) -> Self {
+        Self::Bytes(value.into_bytes())
+    }
+}
+impl From<&'static str> for RequestBody {
+    fn from(value: &'static str) -> Self {
+        Self::Bytes(value.as_bytes().to_vec())
+    }
+}
+impl From<PathBuf> for RequestBody {
+    fn from(value: PathBuf) -> Self {
+        Self::File(value)
+    }
+}
+impl From<&Path> for RequestBody {
+    fn from(value: &Path) -> Self {
+        Self::File(value.to_path_buf())
+    }
+}
+impl Default for RequestBody {
+    fn default() -> Self {
+        Self::Bytes(Vec::new())
+    }
+}
+impl fmt::Debug for RequestBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bytes(bytes) => f.debug_tuple("Bytes").field(&bytes.len()).finish(),
+            Self::File(path) => f.debug_tuple("File").field(path).finish(),
+            Self::Reader { length, .. } => f.debug_struct("Reader").field("length", length).finish(),
+            #[cfg(feature = "stream")]
+            Self::AsyncReader { length, .. } => f
+                .debug_struct("AsyncReader")
+                .field("length", length)
+                .finish(),
+            #[cfg(feature = "stream")]
+            Self::Stream { length, .. } => f.debug_struct("Stream").field("length", length).finish(),
+        }
+    }
+}
+
+/// The content type and body returned by [`Endpoint::request_body`](crate::Endpoint::request_body).
+pub type EndpointBody = (Cow<'static, str>, RequestBody);
+
+/// The size of each chunk read from a body.
+#[cfg(feature = "stream")]
+#[cfg(not(target_arch = "wasm32"))]
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A file or blocking reader, which is opened when first read.
+#[cfg(all(feature = "stream", feature = "tokio"))]
+#[cfg(not(target_arch = "wasm32"))]
+enum BlockingSource {
+    File(PathBuf),
+    Reader(Box<dyn Read + Send + 'static>),
+}
+
+/// Reads a file or blocking reader in chunks, each on the blocking thread pool.
+#[cfg(all(feature = "stream", feature = "tokio"))]
+#[cfg(not(target_arch = "wasm32"))]
+fn blocking_stream(source: BlockingSource) -> BodyStream {
+    Box::pin(futures_util::stream::try_unfold(
+        source,
+        |source| async move {
+            let read = tokio::task::spawn_blocking(move || {
+                let mut reader = match source {
+                    BlockingSource::File(path) => Box::new(std::fs::File::open(path)?),
+                    BlockingSource::Reader(reader) => reader,
+                };
+                let mut buf = vec![0; CHUNK_SIZE];
+                let read = reader.read(&mut buf)?;
+                buf.truncate(read);
+                Ok::<_, std::io::Error>((buf, reader))
+            })
+            .await??;
+
+            Ok(match read {
+                (buf, _) if buf.is_empty() => None,
+                (buf, reader) => Some((bytes::Bytes::from(buf), BlockingSource::Reader(reader))),
+            })
+        },
+    ))
+}
+
+/// Reads an asynchronous reader in chunks.
+#[cfg(feature = "stream")]
+#[cfg(not(target_arch = "wasm32"))]
+fn reader_stream<R>(reader: R) -> BodyStream
+where
+    R: futures_util::io::AsyncRead + Send + Unpin + 'static,
+{
+    use futures_util::io::AsyncReadExt;
+
+    Box::pin(futures_util::stream::try_unfold(
+        reader,
+        |mut reader| async move {
+            let mut buf = vec![0; CHUNK_SIZE];
+            let read = reader.read(&mut buf).await?;
+            if read == 0 {
+                return Ok(None);
+            }
+
+            buf.truncate(read);
+            Ok(Some((bytes::Bytes::from(buf), reader)))
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(all(feature = "stream", feature = "tokio"))]
+    #[test]
+    fn streams_blocking_readers() {
+        use futures_util::TryStreamExt;
+
+        let data: Vec<u8> = (0..CHUNK_SIZE * 2 + 10).map(|x| x as u8).collect();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let chunks: Vec<_> = runtime
+            .block_on(
+                RequestBody::reader(std::io::Cursor::new(data.clone()))
+                    .into_stream()
+                    .unwrap()
+                    .try_collect(),
+            )
+            .unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks.concat(), data);
+
+        let bytes = runtime
+            .block_on(RequestBody::reader(std::io::Cursor::new(data.clone())).into_bytes_async())
+            .unwrap();
+        assert_eq!(bytes, data);
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn reads_blocking_bodies_inline() {
+        let data = b"blocking".to_vec();
+        let future = core::pin::pin!(
+            RequestBody::reader(std::io::Cursor::new(data.clone())).into_bytes_async()
+        );
+        let mut cx = core::task::Context::from_waker(core::task::Waker::noop());
+        let core::task::Poll::Ready(bytes) = future.poll(&mut cx) else {
+            panic!("reading a blocking body should not wait");
+        };
+        assert_eq!(bytes.unwrap(), data);
+    }
+}
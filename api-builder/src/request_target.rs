@@ -0,0 +1,245 @@
+use core::cell::RefCell;
+use std::borrow::Cow;
+
+use http::Uri;
+use percent_encoding::{AsciiSet, CONTROLS, percent_decode_str, utf8_percent_encode};
+use url::{Position, Url};
+
+use crate::{QueryParamPairs, TargetError};
+
+/// Characters escaped within a path, where `/` separates segments and `%` starts an escape.
+const PATH: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'[')
+    .add(b'\\')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// Characters escaped within a single segment.
+const SEGMENT: &AsciiSet = &PATH.add(b'/').add(b'%');
+
+/// Whether `segment` is `.` or `..`, which would be removed or move up a level when the path is normalised.
+fn is_dot_segment(segment: &str) -> bool {
+    matches!(segment, "." | "..")
+}
+
+/// Calls `f` with an empty buffer for building a URL, which is reused on each thread.
+fn with_url_buffer<R>(f: impl FnOnce(&mut String) -> R) -> R {
+    thread_local! {
+        static BUFFER: RefCell<String> = const { RefCell::new(String::new()) };
+    }
+
+    BUFFER.with(|buffer| match buffer.try_borrow_mut() {
+        Ok(mut buffer) => {
+            buffer.clear();
+            f(&mut buffer)
+        }
+        // The buffer is already in use further up the stack
+        Err(_) => f(&mut String::new()),
+    })
+}
+
+/// A part of the path of a [`RequestTarget`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+enum PathPart<'a> {
+    /// Segments separated by `/`, which may already contain escapes.
+    Path(Cow<'a, str>),
+    /// A single segment, which is escaped entirely.
+    Segment(Cow<'a, str>),
+}
+
+/// The path and query of a request, relative to the base URL of a client.
+///
+/// The path is kept as segments and the query as pairs, so neither can change the meaning of the other.
+/// For example, a segment containing `/`, `?` or `#` is escaped instead of starting a new segment, query or fragment.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct RequestTarget<'a> {
+    path: Vec<PathPart<'a>>,
+    query: Option<QueryParamPairs<'a>>,
+}
+impl<'a> RequestTarget<'a> {
+    /// Create an empty target, which is the base URL itself.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a target from a path such as `"pets/1"`.
+    ///
+    /// See [`RequestTarget::path`].
+    pub fn from_path(path: impl Into<Cow<'a, str>>) -> Self {
+        Self::new().path(path)
+    }
+
+    /// Append a path such as `"pets/1"`.
+    ///
+    /// Each `/` separates segments, and leading or repeated slashes are ignored so the path can't escape the base URL.
+    /// A trailing slash is kept when this is the last part of the path.
+    /// Existing escapes such as `%20` are kept, while characters such as `?` and `#` are escaped.
+    /// Segments which are `.` or `..`, including escaped forms such as `%2e%2e`, are rejected with [`TargetError::DotSegment`].
+    pub fn path(mut self, path: impl Into<Cow<'a, str>>) -> Self {
+        self.path.push(PathPart::Path(path.into()));
+        self
+    }
+
+    /// Append a single segment, such as the value of a path parameter.
+    ///
+    /// Every reserved character is escaped, including `/` and `%`.
+    /// A segment of `.` or `..` is rejected with [`TargetError::DotSegment`].
+    pub fn segment(mut self, segment: impl Into<Cow<'a, str>>) -> Self {
+        self.path.push(PathPart::Segment(segment.into()));
+        self
+    }
+
+    /// Set the query parameters.
+    pub fn query(mut self, query: impl Into<Option<QueryParamPairs<'a>>>) -> Self {
+        self.query = query.into();
+        self
+    }
+
+    /// The query parameters.
+    pub fn query_params(&self) -> Option<&QueryParamPairs<'a>> {
+        self.query.as_ref()
+    }
+
+    /// Writes each escaped segment to `output`, with a `/` before each.
+    fn write_segments(&self, output: &mut String) -> Result<(), TargetError> {
+        for (i, part) in self.path.iter().enumerate() {
+            match part {
+                PathPart::Path(path) => {
+                    for segment in path.split('/').filter(|x| !x.is_empty()) {
+                        // Escapes are kept, so `%2e` is also a dot
+                        if is_dot_segment(&percent_decode_str(segment).decode_utf8_lossy()) {
+                            return Err(TargetError::DotSegment(segment.to_owned()));
+                        }
+                        output.push('/');
+                        output.extend(utf8_percent_encode(segment, PATH));
+                    }
+                    if i + 1 == self.path.len() && path.ends_with('/') {
+                        output.push('/');
+                    }
+                }
+                PathPart::Segment(segment) => {
+                    if is_dot_segment(segment) {
+                        return Err(TargetError::DotSegment(segment.to_string()));
+                    }
+                    output.push('/');
+                    output.extend(utf8_percent_encode(segment, SEGMENT));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the encoded query to `output`, which may already contain a query after `start`.
+    fn write_query(&self, output: &mut String, start: usize) {
+        let Some(query) = self.query.as_ref().filter(|x| !x.is_empty()) else {
+            return;
+        };
+        if output.len() == start {
+            output.push('?');
+        }
+        // Pairs after the `?` are separated by `&`
+        let mut serializer = ::url::form_urlencoded::Serializer::for_suffix(output, start + 1);
+        for pair in query.iter() {
+            serializer.append_pair(&pair.key, &pair.value);
+        }
+        serializer.finish();
+    }
+
+    /// Writes the relative target, e.g. `pets/1?limit=10`, to the end of `output`.
+    pub fn write(&self, output: &mut String) -> Result<(), TargetError> {
+        let start = output.len();
+        self.write_segments(output)?;
+        // Relative to the base URL
+        if output[start..].starts_with('/') {
+            output.remove(start);
+        }
+        let start = output.len();
+        self.write_query(output, start);
+        Ok(())
+    }
+
+    /// Append the target to the path of `base`.
+    ///
+    /// Unlike [`Url::join`], the last segment of `base` is kept whether or not it ends with `/`.
+    /// Any query of `base` is kept, followed by the query parameters.
+    pub fn join(&self, base: &Url) -> Result<Uri, TargetError> {
+        with_url_buffer(|output| {
+            output.push_str(&base[..Position::BeforePath]);
+            if self.path.is_empty() {
+                output.push_str(base.path());
+            } else {
+                output.push_str(base.path().trim_end_matches('/'));
+                self.write_segments(output)?;
+            }
+
+            let start = output.len();
+            if let Some(query) = base.query().filter(|x| !x.is_empty()) {
+                output.push('?');
+                output.push_str(query);
+            }
+            self.write_query(output, start);
+
+            // An exact sized copy, which the URI takes ownership of without copying again
+            Ok(Uri::try_from(output.clone()).map_err(http::Error::from)?)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn join(target: RequestTarget<'_>) -> Result<String, TargetError> {
+        let base = Url::parse("https://example.com/v1").unwrap();
+        Ok(target.join(&base)?.to_string())
+    }
+
+    #[test]
+    fn joins_segments_and_query() {
+        let target = RequestTarget::from_path("/pets//")
+            .segment("a/b?c")
+            .query(QueryParamPairs(vec![("limit", "10").into()]));
+        assert_eq!(
+            join(target).unwrap(),
+            "https://example.com/v1/pets/a%2Fb%3Fc?limit=10"
+        );
+    }
+
+    #[test]
+    fn rejects_dot_segments() {
+        for target in [
+            RequestTarget::new().segment(".."),
+            RequestTarget::new().segment("."),
+            RequestTarget::from_path("../admin"),
+            RequestTarget::from_path("pets/./1"),
+            RequestTarget::from_path("%2e%2e/x"),
+            RequestTarget::from_path("%2E."),
+            RequestTarget::from_path("pets/%2e"),
+        ] {
+            assert!(
+                matches!(join(target.clone()), Err(TargetError::DotSegment(_))),
+                "{target:?}"
+            );
+            assert!(target.write(&mut String::new()).is_err());
+        }
+    }
+
+    #[test]
+    fn keeps_other_dots() {
+        let target = RequestTarget::from_path("files/.env/...").segment("a..b");
+        assert_eq!(
+            join(target).unwrap(),
+            "https://example.com/v1/files/.env/.../a..b"
+        );
+    }
+}